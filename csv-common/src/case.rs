@@ -0,0 +1,45 @@
+use convert_case::{Case, Casing};
+use heck::{ToLowerCamelCase, ToSnakeCase};
+
+#[derive(Debug, Default)]
+pub enum HeaderCase {
+    Camel,
+    Snake,
+    Pascal,
+    Kebab,
+    Constant,
+    Cobol,
+    #[default]
+    None,
+}
+
+impl HeaderCase {
+    /// Apply the header case transformation to a header name.
+    pub fn apply(&self, header: &str) -> String {
+        match self {
+            HeaderCase::Camel => header.to_lower_camel_case(),
+            HeaderCase::Snake => header.to_snake_case(),
+            HeaderCase::Pascal => header.to_case(Case::Pascal),
+            HeaderCase::Kebab => header.to_case(Case::Kebab),
+            HeaderCase::Constant => header.to_case(Case::Constant),
+            HeaderCase::Cobol => header.to_case(Case::Cobol),
+            HeaderCase::None => header.to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for HeaderCase {
+    type Error = &'static str;
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "camel" => Ok(HeaderCase::Camel),
+            "snake" => Ok(HeaderCase::Snake),
+            "pascal" => Ok(HeaderCase::Pascal),
+            "kebab" => Ok(HeaderCase::Kebab),
+            "constant" => Ok(HeaderCase::Constant),
+            "cobol" => Ok(HeaderCase::Cobol),
+            "" | "none" => Ok(HeaderCase::None),
+            _ => Err("Invalid header case"),
+        }
+    }
+}
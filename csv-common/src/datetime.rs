@@ -0,0 +1,250 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{
+    DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use fluvio_smartmodule::{eyre, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Date/time parsing and normalization of a column.
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "DatetimeSpecRaw")]
+pub struct DatetimeSpec {
+    formats: Vec<Format>,
+    timezone: Zone,
+    output: Output,
+}
+
+/// JSON form of the `datetime` field of a schema column.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DatetimeSpecRaw {
+    /// Input formats, tried in order.
+    formats: Vec<String>,
+    /// Timezone of values without an offset; UTC when omitted.
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(default)]
+    output: Output,
+}
+
+#[derive(Debug)]
+enum Format {
+    Rfc3339,
+    EpochSeconds,
+    EpochMillis,
+    /// A `strftime` pattern such as `%m/%d/%Y`.
+    Pattern(String),
+}
+
+#[derive(Debug)]
+enum Zone {
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Output {
+    /// RFC 3339 string in UTC.
+    #[default]
+    Rfc3339,
+    /// Milliseconds since the Unix epoch.
+    EpochMillis,
+}
+
+impl TryFrom<DatetimeSpecRaw> for DatetimeSpec {
+    type Error = String;
+
+    fn try_from(raw: DatetimeSpecRaw) -> std::result::Result<Self, Self::Error> {
+        if raw.formats.is_empty() {
+            return Err("datetime `formats` cannot be empty".to_string());
+        }
+        let formats = raw
+            .formats
+            .into_iter()
+            .map(|format| Format::from_name(&format))
+            .collect::<std::result::Result<Vec<Format>, String>>()?;
+        let timezone = match raw.timezone.as_deref() {
+            Some(name) => Zone::parse(name)?,
+            None => Zone::Fixed(FixedOffset::east_opt(0).expect("UTC offset is valid")),
+        };
+
+        Ok(Self {
+            formats,
+            timezone,
+            output: raw.output,
+        })
+    }
+}
+
+impl DatetimeSpec {
+    /// Parse a cell with the first matching format and normalize it.
+    pub fn convert(&self, cell: &str) -> Result<Value> {
+        let instant = self
+            .formats
+            .iter()
+            .find_map(|format| format.parse(cell, &self.timezone))
+            .ok_or_else(|| eyre!("`{}` does not match any datetime format", cell))?;
+
+        Ok(match self.output {
+            Output::Rfc3339 => Value::String(instant.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            Output::EpochMillis => Value::from(instant.timestamp_millis()),
+        })
+    }
+}
+
+impl Format {
+    fn from_name(format: &str) -> std::result::Result<Self, String> {
+        match format.to_lowercase().as_str() {
+            "rfc3339" => Ok(Format::Rfc3339),
+            "epoch_seconds" => Ok(Format::EpochSeconds),
+            "epoch_millis" => Ok(Format::EpochMillis),
+            _ if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) => {
+                Err(format!("invalid datetime format `{}`", format))
+            }
+            _ => Ok(Format::Pattern(format.to_string())),
+        }
+    }
+
+    /// Parse a cell into an instant; values without an offset are in the given zone.
+    fn parse(&self, cell: &str, zone: &Zone) -> Option<DateTime<Utc>> {
+        match self {
+            Format::Rfc3339 => DateTime::parse_from_rfc3339(cell)
+                .ok()
+                .map(|instant| instant.with_timezone(&Utc)),
+            Format::EpochSeconds => epoch(cell, 1000.0),
+            Format::EpochMillis => epoch(cell, 1.0),
+            Format::Pattern(pattern) => {
+                if let Ok(instant) = DateTime::parse_from_str(cell, pattern) {
+                    return Some(instant.with_timezone(&Utc));
+                }
+                let naive = NaiveDateTime::parse_from_str(cell, pattern)
+                    .or_else(|_| {
+                        NaiveDate::parse_from_str(cell, pattern)
+                            .map(|date| date.and_time(NaiveTime::MIN))
+                    })
+                    .ok()?;
+                zone.localize(&naive)
+            }
+        }
+    }
+}
+
+impl Zone {
+    fn parse(name: &str) -> std::result::Result<Self, String> {
+        if let Ok(instant) =
+            DateTime::parse_from_str(&format!("2000-01-01 00:00 {}", name), "%F %R %:z")
+        {
+            return Ok(Zone::Fixed(*instant.offset()));
+        }
+        name.parse::<Tz>()
+            .map(Zone::Named)
+            .map_err(|_| format!("unknown timezone `{}`", name))
+    }
+
+    /// Resolve a local date/time; ambiguous times take the earlier instant and
+    /// times skipped by a DST change do not resolve.
+    fn localize(&self, naive: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Fixed(offset) => offset
+                .from_local_datetime(naive)
+                .earliest()
+                .map(|instant| instant.with_timezone(&Utc)),
+            Zone::Named(tz) => tz
+                .from_local_datetime(naive)
+                .earliest()
+                .map(|instant| instant.with_timezone(&Utc)),
+        }
+    }
+}
+
+/// Parse an epoch value, in seconds or milliseconds depending on `millis_per_unit`.
+fn epoch(cell: &str, millis_per_unit: f64) -> Option<DateTime<Utc>> {
+    let millis = match cell.parse::<i64>() {
+        Ok(value) => value.checked_mul(millis_per_unit as i64)?,
+        Err(_) => {
+            let value = cell.parse::<f64>().ok().filter(|value| value.is_finite())?;
+            (value * millis_per_unit).round() as i64
+        }
+    };
+    DateTime::from_timestamp_millis(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(raw: &str) -> DatetimeSpec {
+        serde_json::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn test_patterns_in_order() {
+        let spec = spec(r#"{"formats": ["%m/%d/%Y", "%Y-%m-%d %H:%M", "epoch_seconds"]}"#);
+
+        assert_eq!(
+            spec.convert("03/04/2024").unwrap(),
+            json!("2024-03-04T00:00:00Z")
+        );
+        assert_eq!(
+            spec.convert("2024-03-04 12:00").unwrap(),
+            json!("2024-03-04T12:00:00Z")
+        );
+        assert_eq!(
+            spec.convert("1709553600").unwrap(),
+            json!("2024-03-04T12:00:00Z")
+        );
+        assert_eq!(
+            spec.convert("next tuesday").unwrap_err().to_string(),
+            "`next tuesday` does not match any datetime format"
+        );
+    }
+
+    #[test]
+    fn test_timezone_and_epoch_millis() {
+        let named = spec(
+            r#"{"formats": ["%Y-%m-%d %H:%M"], "timezone": "America/New_York", "output": "epoch_millis"}"#,
+        );
+        // EST is UTC-5 in March before the DST change
+        assert_eq!(
+            named.convert("2024-03-04 07:00").unwrap(),
+            json!(1709553600000i64)
+        );
+        // Skipped by the DST change
+        assert!(named.convert("2024-03-10 02:30").is_err());
+
+        let fixed = spec(r#"{"formats": ["%d.%m.%Y %H:%M"], "timezone": "+02:00"}"#);
+        assert_eq!(
+            fixed.convert("04.03.2024 14:00").unwrap(),
+            json!("2024-03-04T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_explicit_offsets_win() {
+        let spec =
+            spec(r#"{"formats": ["rfc3339", "%Y-%m-%d %H:%M %z"], "timezone": "Asia/Tokyo"}"#);
+
+        assert_eq!(
+            spec.convert("2024-03-04T13:00:00.250+01:00").unwrap(),
+            json!("2024-03-04T12:00:00.250Z")
+        );
+        assert_eq!(
+            spec.convert("2024-03-04 12:00 +0000").unwrap(),
+            json!("2024-03-04T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_specs() {
+        let parse = |raw: &str| serde_json::from_str::<DatetimeSpec>(raw);
+
+        assert!(parse(r#"{"formats": []}"#).is_err());
+        assert!(parse(r#"{"formats": ["%Q"]}"#).is_err());
+        assert!(parse(r#"{"formats": ["%F"], "timezone": "Mars/Olympus"}"#).is_err());
+        assert!(parse(r#"{"formats": ["%F"], "output": "unix"}"#).is_err());
+    }
+}
@@ -0,0 +1,633 @@
+use std::borrow::Cow;
+
+use csv::{ByteRecord, QuoteStyle, ReaderBuilder, StringRecord, Terminator, Trim, WriterBuilder};
+use fluvio_smartmodule::{eyre, Result};
+use serde::Deserialize;
+
+use crate::params::DEFAULT_DELIMITER;
+
+/// Control byte standing for a multi-byte delimiter in the input of the CSV reader.
+const DELIMITER_SUBSTITUTE: u8 = 0x1F;
+/// Control byte standing for a multi-character comment prefix in the input of the CSV reader.
+const COMMENT_SUBSTITUTE: u8 = 0x1E;
+
+/// CSV dialect used to configure the reader, or the writer of `json-csv`.
+#[derive(Debug)]
+pub struct Dialect {
+    pub delimiter: u8,
+    /// Sniff the delimiter of each record; `delimiter` is the fallback.
+    pub auto_delimiter: bool,
+    quote: u8,
+    quoting: bool,
+    /// When the writer quotes fields.
+    quote_style: QuoteStyle,
+    escape: Option<u8>,
+    double_quote: bool,
+    comment: Option<u8>,
+    /// Record terminator; by default the reader accepts any line break and the writer
+    /// ends records with `\n`.
+    terminator: Option<Terminator>,
+    trim: Trim,
+    /// Multi-byte delimiter, replaced with `DELIMITER_SUBSTITUTE` before parsing.
+    delimiter_text: Option<String>,
+    /// Multi-character comment prefix, replaced with `COMMENT_SUBSTITUTE` at line starts.
+    comment_text: Option<String>,
+}
+
+/// A dialect character: a single ASCII byte handled by the CSV reader, or a longer text
+/// handled by substitution.
+enum Token {
+    Byte(u8),
+    Text(String),
+}
+
+/// JSON form of the `dialect` param.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DialectSpec {
+    delimiter: Option<String>,
+    quote: Option<String>,
+    quoting: Option<bool>,
+    quote_style: Option<String>,
+    escape: Option<String>,
+    double_quote: Option<bool>,
+    comment: Option<String>,
+    terminator: Option<String>,
+    trim: Option<String>,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Self {
+            delimiter: DEFAULT_DELIMITER,
+            auto_delimiter: false,
+            quote: b'"',
+            quoting: true,
+            quote_style: QuoteStyle::Necessary,
+            escape: None,
+            double_quote: true,
+            comment: None,
+            terminator: None,
+            trim: Trim::All,
+            delimiter_text: None,
+            comment_text: None,
+        }
+    }
+}
+
+impl Dialect {
+    pub fn with_delimiter(delimiter: u8) -> Self {
+        Self {
+            delimiter,
+            ..Self::default()
+        }
+    }
+
+    /// Build the dialect from the `dialect` and `delimiter` params.
+    pub fn from_params(dialect: Option<&str>, delimiter: Option<&str>) -> Result<Self> {
+        let spec: DialectSpec = match dialect {
+            Some(raw) => serde_json::from_str(raw)
+                .map_err(|err| eyre!("cannot parse `dialect` param: {}", err))?,
+            None => DialectSpec::default(),
+        };
+
+        let (delimiter, auto_delimiter) = match (spec.delimiter.as_deref(), delimiter) {
+            (Some(_), Some(_)) => {
+                return Err(eyre!("`delimiter` is set both as a param and in `dialect`"))
+            }
+            (Some(v), None) | (None, Some(v)) if v.eq_ignore_ascii_case("auto") => {
+                (Token::Byte(DEFAULT_DELIMITER), true)
+            }
+            (Some(v), None) | (None, Some(v)) => (parse_token("delimiter", v)?, false),
+            (None, None) => (Token::Byte(DEFAULT_DELIMITER), false),
+        };
+        let (delimiter, delimiter_text) = match delimiter {
+            Token::Byte(byte) => (byte, None),
+            Token::Text(text) => (DELIMITER_SUBSTITUTE, Some(text)),
+        };
+        let (comment, comment_text) = match spec.comment.as_deref().map(|v| parse_token("comment", v)) {
+            Some(Ok(Token::Byte(byte))) => (Some(byte), None),
+            Some(Ok(Token::Text(text))) => (Some(COMMENT_SUBSTITUTE), Some(text)),
+            Some(Err(err)) => return Err(err),
+            None => (None, None),
+        };
+
+        let quoting = spec.quoting.unwrap_or(true);
+        let quote_style = match spec.quote_style.as_deref() {
+            Some(v) if !quoting && !v.eq_ignore_ascii_case("never") => {
+                return Err(eyre!("`quoting` is disabled but `quote_style` is `{}`", v))
+            }
+            Some(v) => parse_quote_style(v)?,
+            None if !quoting => QuoteStyle::Never,
+            None => QuoteStyle::Necessary,
+        };
+
+        let defaults = Self::default();
+        let dialect = Self {
+            delimiter,
+            auto_delimiter,
+            quote: match spec.quote.as_deref() {
+                Some(v) => parse_byte("quote", v)?,
+                None => defaults.quote,
+            },
+            quoting,
+            quote_style,
+            escape: spec.escape.as_deref().map(|v| parse_byte("escape", v)).transpose()?,
+            double_quote: spec.double_quote.unwrap_or(defaults.double_quote),
+            comment,
+            terminator: spec.terminator.as_deref().map(parse_terminator).transpose()?.flatten(),
+            trim: match spec.trim.as_deref() {
+                Some(v) => parse_trim(v)?,
+                None => defaults.trim,
+            },
+            delimiter_text,
+            comment_text,
+        };
+
+        // A sniffed delimiter is checked against the other characters when it is picked
+        if !dialect.auto_delimiter {
+            dialect.check_delimiter(dialect.delimiter)?;
+        }
+        if dialect.comment_text.is_some() && dialect.comment_text == dialect.delimiter_text {
+            return Err(eyre!("`comment` and `delimiter` must be different characters"));
+        }
+
+        Ok(dialect)
+    }
+
+    /// Check that a delimiter differs from the other characters of the dialect.
+    fn check_delimiter(&self, delimiter: u8) -> Result<()> {
+        if self.quoting && self.quote == delimiter {
+            return Err(eyre!("`quote` and `delimiter` must be different characters"));
+        }
+        if self.escape == Some(delimiter) {
+            return Err(eyre!("`escape` and `delimiter` must be different characters"));
+        }
+        if self.is_terminator(delimiter) {
+            return Err(eyre!("`terminator` and `delimiter` must be different characters"));
+        }
+        if self.comment == Some(delimiter) {
+            return Err(eyre!("`comment` and `delimiter` must be different characters"));
+        }
+        Ok(())
+    }
+
+    /// Whether a sniffed delimiter is free of the other characters of the dialect.
+    pub fn allows_delimiter(&self, delimiter: u8) -> bool {
+        self.check_delimiter(delimiter).is_ok()
+    }
+
+    /// Rewrite the input for the CSV reader: a multi-byte delimiter becomes a single control
+    /// byte, and so does a multi-character comment prefix at the start of a line.
+    ///
+    /// Line numbers are kept; byte positions are mapped back with `input_offset`.
+    pub fn prepare<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        if self.delimiter_text.is_none() && self.comment_text.is_none() {
+            return Ok(Cow::Borrowed(input));
+        }
+        let delimiter = self.delimiter_text.as_deref().map(str::as_bytes);
+        let comment = self.comment_text.as_deref().map(str::as_bytes);
+
+        let mut prepared = Vec::with_capacity(input.len());
+        let mut rest = input;
+        let mut line_start = true;
+        while let Some(&byte) = rest.first() {
+            let (output, length) = match (comment, delimiter) {
+                (Some(prefix), _) if line_start && rest.starts_with(prefix) => {
+                    (COMMENT_SUBSTITUTE, prefix.len())
+                }
+                (_, Some(text)) if rest.starts_with(text) => (DELIMITER_SUBSTITUTE, text.len()),
+                _ if (delimiter.is_some() && byte == DELIMITER_SUBSTITUTE)
+                    || (comment.is_some() && byte == COMMENT_SUBSTITUTE) =>
+                {
+                    return Err(eyre!(
+                        "input contains the control byte {:#04x}, which stands for the multi-byte delimiter or comment",
+                        byte
+                    ));
+                }
+                _ => (byte, 1),
+            };
+            prepared.push(output);
+            rest = &rest[length..];
+            line_start = length == 1 && self.is_terminator(byte);
+        }
+
+        Ok(Cow::Owned(prepared))
+    }
+
+    /// Put back the multi-byte delimiter and comment prefix substituted inside quoted cells.
+    pub fn restore(&self, record: StringRecord) -> StringRecord {
+        let substituted = |cell: &str| {
+            (self.delimiter_text.is_some() && cell.contains(char::from(DELIMITER_SUBSTITUTE)))
+                || (self.comment_text.is_some() && cell.contains(char::from(COMMENT_SUBSTITUTE)))
+        };
+        if !record.iter().any(substituted) {
+            return record;
+        }
+
+        let mut restored: StringRecord = record.iter().map(|cell| self.restore_text(cell)).collect();
+        restored.set_position(record.position().cloned());
+        restored
+    }
+
+    /// Put back the multi-byte delimiter and comment prefix substituted in a text.
+    pub fn restore_text(&self, text: &str) -> String {
+        let mut restored = text.to_string();
+        if let Some(delimiter) = &self.delimiter_text {
+            restored = restored.replace(char::from(DELIMITER_SUBSTITUTE), delimiter);
+        }
+        if let Some(comment) = &self.comment_text {
+            restored = restored.replace(char::from(COMMENT_SUBSTITUTE), comment);
+        }
+        restored
+    }
+
+    /// Byte offset in the original input of an offset in the prepared input.
+    pub fn input_offset(&self, prepared: &[u8], offset: u64) -> u64 {
+        let end = (offset as usize).min(prepared.len());
+        let extra = |text: &Option<String>| text.as_ref().map_or(0, |text| text.len() as u64 - 1);
+        prepared[..end].iter().fold(offset, |offset, &byte| match byte {
+            DELIMITER_SUBSTITUTE => offset + extra(&self.delimiter_text),
+            COMMENT_SUBSTITUTE => offset + extra(&self.comment_text),
+            _ => offset,
+        })
+    }
+
+    /// Original text of the record starting at `start` in the prepared input, without its
+    /// terminator. Quoted cells spanning several lines are kept whole.
+    pub fn raw_record(&self, prepared: &[u8], start: u64) -> String {
+        let rest = &prepared[(start as usize).min(prepared.len())..];
+        let mut reader = self
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(rest);
+        // The reader stops at the end of the record even when it cannot be parsed
+        let _ = reader.read_byte_record(&mut ByteRecord::new());
+        let end = (reader.position().byte() as usize).min(rest.len());
+
+        let mut raw = &rest[..end];
+        while let Some((&last, line)) = raw.split_last() {
+            if !self.is_terminator(last) {
+                break;
+            }
+            raw = line;
+            if let Some(Terminator::Any(_)) = self.terminator {
+                break;
+            }
+        }
+        self.restore_text(&String::from_utf8_lossy(raw))
+    }
+
+    /// Whether a byte ends a record.
+    pub fn is_terminator(&self, byte: u8) -> bool {
+        match self.terminator {
+            Some(Terminator::Any(terminator)) => byte == terminator,
+            _ => byte == b'\n' || byte == b'\r',
+        }
+    }
+
+    /// Reader builder configured with the dialect.
+    pub fn reader_builder(&self) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .quoting(self.quoting)
+            .escape(self.escape)
+            .double_quote(self.double_quote)
+            .comment(self.comment)
+            .terminator(self.terminator.unwrap_or(Terminator::CRLF))
+            .trim(self.trim);
+        builder
+    }
+
+    /// Writer builder configured with the dialect; `comment` and `trim` only apply to
+    /// reading.
+    pub fn writer_builder(&self) -> WriterBuilder {
+        let mut builder = WriterBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .quote_style(self.quote_style)
+            .escape(self.escape.unwrap_or(b'\\'))
+            .double_quote(self.double_quote)
+            .terminator(self.terminator.unwrap_or(Terminator::Any(b'\n')))
+            .has_headers(false);
+        builder
+    }
+
+    /// Field text for the CSV writer: a multi-byte delimiter becomes the control byte
+    /// standing for it, so that fields holding it get quoted.
+    pub fn prepare_field<'a>(&self, field: &'a str) -> Result<Cow<'a, str>> {
+        let Some(delimiter) = &self.delimiter_text else {
+            return Ok(Cow::Borrowed(field));
+        };
+        if field.contains(char::from(DELIMITER_SUBSTITUTE)) {
+            return Err(eyre!(
+                "field contains the control byte {:#04x}, which stands for the multi-byte delimiter",
+                DELIMITER_SUBSTITUTE
+            ));
+        }
+        Ok(Cow::Owned(
+            field.replace(delimiter.as_str(), &char::from(DELIMITER_SUBSTITUTE).to_string()),
+        ))
+    }
+
+    /// Put back the multi-byte delimiter in the output of the CSV writer.
+    pub fn restore_output(&self, output: Vec<u8>) -> Vec<u8> {
+        let Some(delimiter) = &self.delimiter_text else {
+            return output;
+        };
+        let mut restored = Vec::with_capacity(output.len());
+        for byte in output {
+            match byte {
+                DELIMITER_SUBSTITUTE => restored.extend_from_slice(delimiter.as_bytes()),
+                byte => restored.push(byte),
+            }
+        }
+        restored
+    }
+}
+
+/// Parse a delimiter or comment prefix: a single ASCII character, or any longer text.
+fn parse_token(name: &str, value: &str) -> Result<Token> {
+    let unescaped = unescape(value);
+    if unescaped.contains(['\n', '\r']) {
+        return Err(eyre!("invalid `{}`: `{}` cannot contain a line break", name, value));
+    }
+    match unescaped.as_bytes() {
+        [byte] if byte.is_ascii() => Ok(Token::Byte(*byte)),
+        [] => Err(eyre!("invalid `{}`: value is empty", name)),
+        _ => Ok(Token::Text(unescaped.to_string())),
+    }
+}
+
+/// Parse a single ASCII character, accepting escapes such as `\t` and names such as `tab`.
+fn parse_byte(name: &str, value: &str) -> Result<u8> {
+    match unescape(value).as_bytes() {
+        [byte] if byte.is_ascii() => Ok(*byte),
+        [] => Err(eyre!("invalid `{}`: value is empty", name)),
+        _ => Err(eyre!(
+            "invalid `{}`: `{}` is not a single ASCII character",
+            name,
+            value
+        )),
+    }
+}
+
+fn unescape(value: &str) -> &str {
+    match value {
+        "\\t" | "tab" => "\t",
+        "\\n" => "\n",
+        "\\r" => "\r",
+        "\\\\" => "\\",
+        "\\0" => "\0",
+        "space" => " ",
+        v => v,
+    }
+}
+
+/// Parse the `terminator`; `default` leaves the reader and writer defaults.
+fn parse_terminator(value: &str) -> Result<Option<Terminator>> {
+    match value.to_lowercase().as_str() {
+        "default" => Ok(None),
+        "crlf" => Ok(Some(Terminator::CRLF)),
+        _ => Ok(Some(Terminator::Any(parse_byte("terminator", value)?))),
+    }
+}
+
+fn parse_quote_style(value: &str) -> Result<QuoteStyle> {
+    match value.to_lowercase().as_str() {
+        "necessary" => Ok(QuoteStyle::Necessary),
+        "always" => Ok(QuoteStyle::Always),
+        "non_numeric" => Ok(QuoteStyle::NonNumeric),
+        "never" => Ok(QuoteStyle::Never),
+        _ => Err(eyre!(
+            "invalid `quote_style`: expected `necessary`, `always`, `non_numeric` or `never`, got `{}`",
+            value
+        )),
+    }
+}
+
+fn parse_trim(value: &str) -> Result<Trim> {
+    match value.to_lowercase().as_str() {
+        "all" => Ok(Trim::All),
+        "headers" => Ok(Trim::Headers),
+        "fields" => Ok(Trim::Fields),
+        "none" => Ok(Trim::None),
+        _ => Err(eyre!(
+            "invalid `trim`: expected `all`, `headers`, `fields` or `none`, got `{}`",
+            value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(dialect: &Dialect, input: &str) -> Vec<Vec<String>> {
+        dialect
+            .reader_builder()
+            .has_headers(false)
+            .from_reader(input.as_bytes())
+            .records()
+            .map(|record| record.unwrap().iter().map(String::from).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_escaped_delimiter() {
+        let from_param = Dialect::from_params(None, Some("\\t")).unwrap();
+        assert_eq!(from_param.delimiter, b'\t');
+
+        let from_dialect = Dialect::from_params(Some(r#"{"delimiter": "tab"}"#), None).unwrap();
+        assert_eq!(from_dialect.delimiter, b'\t');
+    }
+
+    #[test]
+    fn test_full_dialect() {
+        let raw = r##"{
+            "delimiter": "|",
+            "quote": "'",
+            "escape": "\\",
+            "double_quote": false,
+            "comment": "#",
+            "trim": "none"
+        }"##;
+        let dialect = Dialect::from_params(Some(raw), None).unwrap();
+
+        let rows = read_all(&dialect, "# generated\na| b|'c\\'d'\n");
+
+        assert_eq!(rows, vec![vec!["a", " b", "c'd"]]);
+    }
+
+    #[test]
+    fn test_custom_terminator() {
+        let raw = r#"{"delimiter": "|", "terminator": "~"}"#;
+        let dialect = Dialect::from_params(Some(raw), None).unwrap();
+
+        let rows = read_all(&dialect, "a|b~c|d~");
+
+        assert_eq!(rows, vec![vec!["a", "b"], vec!["c", "d"]]);
+    }
+
+    fn read_prepared(dialect: &Dialect, input: &str) -> Vec<Vec<String>> {
+        let prepared = dialect.prepare(input.as_bytes()).unwrap();
+        dialect
+            .reader_builder()
+            .has_headers(false)
+            .from_reader(prepared.as_ref())
+            .records()
+            .map(|record| dialect.restore(record.unwrap()).iter().map(String::from).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_multi_byte_delimiter_and_comment() {
+        let raw = r#"{"delimiter": "::", "comment": "//"}"#;
+        let dialect = Dialect::from_params(Some(raw), None).unwrap();
+
+        let rows = read_prepared(&dialect, "// generated\na::b::\"c::d // e\"\nf:g::h::\n");
+        assert_eq!(
+            rows,
+            vec![vec!["a", "b", "c::d // e"], vec!["f:g", "h", ""]]
+        );
+
+        let dialect = Dialect::from_params(None, Some("§")).unwrap();
+        assert_eq!(read_prepared(&dialect, "a§b§é\n"), vec![vec!["a", "b", "é"]]);
+        assert!(dialect.prepare(b"a\x1fb").is_err());
+
+        // Single ASCII characters are left to the CSV reader
+        let dialect = Dialect::from_params(None, Some(";")).unwrap();
+        assert!(matches!(dialect.prepare(b"a;b\x1f"), Ok(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn test_raw_record() {
+        let dialect = Dialect::default();
+        let input = b"a,b\r\n\"x\ny\",z\r\nlast";
+        assert_eq!(dialect.raw_record(input, 0), "a,b");
+        assert_eq!(dialect.raw_record(input, 5), "\"x\ny\",z");
+        assert_eq!(dialect.raw_record(input, 14), "last");
+
+        let dialect = Dialect::from_params(Some(r#"{"delimiter": "::", "terminator": "~"}"#), None)
+            .unwrap();
+        let input = dialect.prepare(b"a::b~c::\"d~e\"~").unwrap();
+        assert_eq!(dialect.raw_record(&input, 4), "c::\"d~e\"");
+    }
+
+    fn write_all(dialect: &Dialect, rows: &[&[&str]]) -> String {
+        let mut writer = dialect.writer_builder().from_writer(Vec::new());
+        for row in rows {
+            let fields: Vec<Cow<str>> = row
+                .iter()
+                .map(|field| dialect.prepare_field(field).unwrap())
+                .collect();
+            writer.write_record(fields.iter().map(|field| field.as_bytes())).unwrap();
+        }
+        String::from_utf8(dialect.restore_output(writer.into_inner().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_writer_default_dialect() {
+        let dialect = Dialect::default();
+
+        let text = write_all(&dialect, &[&["a", "b,c"], &["say \"hi\"", ""]]);
+
+        assert_eq!(text, "a,\"b,c\"\n\"say \"\"hi\"\"\",\n");
+    }
+
+    #[test]
+    fn test_writer_full_dialect() {
+        let raw = r##"{
+            "delimiter": "tab",
+            "quote": "'",
+            "quote_style": "always",
+            "escape": "\\",
+            "double_quote": false,
+            "comment": "#",
+            "terminator": "crlf",
+            "trim": "none"
+        }"##;
+        let dialect = Dialect::from_params(Some(raw), None).unwrap();
+
+        let text = write_all(&dialect, &[&["a", "it's"]]);
+
+        assert_eq!(text, "'a'\t'it\\'s'\r\n");
+        assert_eq!(read_all(&dialect, &text), vec![vec!["a", "it's"]]);
+    }
+
+    #[test]
+    fn test_writer_quoting_disabled() {
+        let dialect = Dialect::from_params(Some(r#"{"quoting": false}"#), Some(";")).unwrap();
+
+        let text = write_all(&dialect, &[&["a b", "\"c\""]]);
+
+        assert_eq!(text, "a b;\"c\"\n");
+        assert!(
+            Dialect::from_params(Some(r#"{"quoting": false, "quote_style": "always"}"#), None)
+                .is_err()
+        );
+        assert!(Dialect::from_params(Some(r#"{"quote_style": "sometimes"}"#), None).is_err());
+    }
+
+    #[test]
+    fn test_writer_multi_byte_delimiter() {
+        let dialect = Dialect::from_params(None, Some("::")).unwrap();
+
+        let text = write_all(&dialect, &[&["a", "b::c", "é"]]);
+
+        assert_eq!(text, "a::\"b::c\"::é\n");
+        assert_eq!(read_prepared(&dialect, &text), vec![vec!["a", "b::c", "é"]]);
+        assert!(dialect.prepare_field("a\x1fb").is_err());
+    }
+
+    #[test]
+    fn test_auto_delimiter() {
+        let dialect = Dialect::from_params(Some(r#"{"delimiter": "auto", "quote": "'"}"#), None)
+            .unwrap();
+        assert!(dialect.auto_delimiter);
+        assert!(dialect.allows_delimiter(b';'));
+        assert!(!dialect.allows_delimiter(b'\''));
+
+        let err = Dialect::from_params(
+            Some(r#"{"delimiter": "auto", "quoting": false, "quote_style": "always"}"#),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "`quoting` is disabled but `quote_style` is `always`");
+    }
+
+    #[test]
+    fn test_rejects_unsupported_values() {
+        let err = Dialect::from_params(Some(r#"{"quote": "«"}"#), None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid `quote`: `«` is not a single ASCII character"
+        );
+
+        assert!(Dialect::from_params(Some(r#"{"delimiter": "::", "comment": "::"}"#), None).is_err());
+        assert!(Dialect::from_params(None, Some("a\nb")).is_err());
+        assert!(Dialect::from_params(None, Some("\\n")).is_err());
+        assert!(Dialect::from_params(Some(r#"{"trim": "both"}"#), None).is_err());
+        assert!(Dialect::from_params(Some(r#"{"separator": ";"}"#), None).is_err());
+        assert!(Dialect::from_params(Some(r#"{"quote": ","}"#), None).is_err());
+        assert!(Dialect::from_params(Some(r#"{"delimiter": ";"}"#), Some(";")).is_err());
+    }
+
+    #[test]
+    fn test_rejects_conflicting_characters() {
+        let err = Dialect::from_params(Some(r#"{"delimiter": "|", "terminator": "|"}"#), None)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`terminator` and `delimiter` must be different characters"
+        );
+
+        let err = Dialect::from_params(Some(r#"{"delimiter": ";", "escape": ";"}"#), None)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "`escape` and `delimiter` must be different characters");
+    }
+}
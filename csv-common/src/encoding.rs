@@ -0,0 +1,119 @@
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, UTF_8};
+use fluvio_smartmodule::{eyre, Result};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Character encoding of the CSV input.
+#[derive(Debug, Default)]
+pub enum InputEncoding {
+    /// UTF-8, with a leading BOM removed.
+    #[default]
+    Utf8,
+    /// Detected from the BOM, falling back to UTF-8.
+    Auto,
+    /// An explicit encoding such as `windows-1252` or `utf-16le`.
+    Label(&'static Encoding),
+}
+
+impl InputEncoding {
+    pub fn parse(value: &str) -> Result<Self> {
+        if value.eq_ignore_ascii_case("auto") {
+            return Ok(InputEncoding::Auto);
+        }
+        match Encoding::for_label(value.trim().as_bytes()) {
+            Some(encoding) if encoding == UTF_8 => Ok(InputEncoding::Utf8),
+            Some(encoding) => Ok(InputEncoding::Label(encoding)),
+            None => Err(eyre!(
+                "invalid `encoding` param: unsupported encoding `{}`",
+                value
+            )),
+        }
+    }
+
+    /// Transcode the input into UTF-8 and remove its BOM.
+    pub fn decode<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        let encoding = match self {
+            InputEncoding::Utf8 => return Ok(Cow::Borrowed(strip_utf8_bom(input))),
+            InputEncoding::Auto => match Encoding::for_bom(input) {
+                Some((encoding, bom_length)) if encoding != UTF_8 => {
+                    return transcode(encoding, &input[bom_length..]);
+                }
+                _ => return Ok(Cow::Borrowed(strip_utf8_bom(input))),
+            },
+            InputEncoding::Label(encoding) => *encoding,
+        };
+
+        // Only a BOM matching the explicit encoding is removed
+        match Encoding::for_bom(input) {
+            Some((bom_encoding, bom_length)) if bom_encoding == encoding => {
+                transcode(encoding, &input[bom_length..])
+            }
+            _ => transcode(encoding, input),
+        }
+    }
+}
+
+fn strip_utf8_bom(input: &[u8]) -> &[u8] {
+    input.strip_prefix(UTF8_BOM).unwrap_or(input)
+}
+
+fn transcode<'a>(encoding: &'static Encoding, input: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+    let (text, had_errors) = encoding.decode_without_bom_handling(input);
+    if had_errors {
+        return Err(eyre!("input is not valid {}", encoding.name()));
+    }
+    Ok(match text {
+        Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+        Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+        let mut bytes = if bom { vec![0xFF, 0xFE] } else { vec![] };
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        bytes
+    }
+
+    #[test]
+    fn test_utf8_bom_is_stripped() {
+        let decoded = InputEncoding::Utf8.decode(b"\xEF\xBB\xBFname\n").unwrap();
+        assert_eq!(decoded.as_ref(), b"name\n");
+
+        let decoded = InputEncoding::Auto.decode(b"\xEF\xBB\xBFname\n").unwrap();
+        assert_eq!(decoded.as_ref(), b"name\n");
+    }
+
+    #[test]
+    fn test_auto_detects_utf16_bom() {
+        let input = utf16le("café,1\n", true);
+        let decoded = InputEncoding::Auto.decode(&input).unwrap();
+        assert_eq!(decoded.as_ref(), "café,1\n".as_bytes());
+    }
+
+    #[test]
+    fn test_explicit_label() {
+        let windows_1252 = InputEncoding::parse("windows-1252").unwrap();
+        let decoded = windows_1252.decode(b"caf\xE9,\x80 5\n").unwrap();
+        assert_eq!(decoded.as_ref(), "café,€ 5\n".as_bytes());
+
+        let utf16 = InputEncoding::parse("UTF-16LE").unwrap();
+        let input = utf16le("a,b\n", false);
+        let decoded = utf16.decode(&input).unwrap();
+        assert_eq!(decoded.as_ref(), b"a,b\n");
+    }
+
+    #[test]
+    fn test_rejects_invalid_input_and_labels() {
+        assert!(InputEncoding::parse("ebcdic-42").is_err());
+
+        let utf16 = InputEncoding::parse("utf-16le").unwrap();
+        let err = utf16.decode(b"a,b\n\x00").unwrap_err();
+        assert_eq!(err.to_string(), "input is not valid UTF-16LE");
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt::Display;
+
+use csv::{ErrorKind, Position, StringRecord};
+use fluvio_smartmodule::{eyre, Result};
+use serde_json::{json, Map, Value};
+
+use crate::dialect::Dialect;
+
+/// What to do with rows that cannot be parsed or converted.
+#[derive(Debug, Default, Clone, Copy)]
+pub enum OnError {
+    /// Stop processing and return the error.
+    #[default]
+    Fail,
+    /// Drop the row.
+    Skip,
+    /// Output an error object in place of the row.
+    Emit,
+}
+
+impl TryFrom<String> for OnError {
+    type Error = &'static str;
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "" | "fail" => Ok(OnError::Fail),
+            "skip" => Ok(OnError::Skip),
+            "emit" => Ok(OnError::Emit),
+            _ => Err("Invalid error policy"),
+        }
+    }
+}
+
+impl OnError {
+    /// Apply the policy to a row error.
+    ///
+    /// Returns the error object to output in place of the row, or `None` to drop it.
+    /// `input` is the input of the CSV reader, as prepared by the dialect.
+    pub fn handle(self, error: RowError, input: &[u8], dialect: &Dialect) -> Result<Option<Value>> {
+        let byte = dialect.input_offset(input, error.byte);
+        self.report(
+            error.message,
+            format_args!("line {}, byte {}", error.line, byte),
+            || {
+                json!({
+                    "line": error.line,
+                    "byte": byte,
+                    "raw": dialect.raw_record(input, error.byte),
+                })
+            },
+        )
+    }
+
+    /// Apply the policy to an error found at `location` of the input, such as
+    /// `line 2, byte 30`.
+    ///
+    /// `details` builds the JSON object of the location fields added to an emitted
+    /// error object, after `error`.
+    pub fn report(
+        self,
+        message: String,
+        location: impl Display,
+        details: impl FnOnce() -> Value,
+    ) -> Result<Option<Value>> {
+        match self {
+            OnError::Fail => Err(eyre!("{}: {}", location, message)),
+            OnError::Skip => Ok(None),
+            OnError::Emit => {
+                let mut error_object = Map::new();
+                error_object.insert("error".to_string(), Value::String(message));
+                if let Value::Object(details) = details() {
+                    error_object.extend(details);
+                }
+                Ok(Some(Value::Object(error_object)))
+            }
+        }
+    }
+}
+
+/// A row that failed to parse or convert, with its location in the CSV input.
+#[derive(Debug)]
+pub struct RowError {
+    line: u64,
+    byte: u64,
+    message: String,
+}
+
+impl RowError {
+    /// Error raised by the CSV reader.
+    pub fn from_csv(err: csv::Error) -> Self {
+        let message = match err.kind() {
+            ErrorKind::UnequalLengths {
+                expected_len, len, ..
+            } => format!("found {} fields, expected {}", len, expected_len),
+            ErrorKind::Utf8 { err, .. } => format!("invalid UTF-8: {}", err),
+            _ => err.to_string(),
+        };
+        Self::new(err.position(), message)
+    }
+
+    /// Error raised while converting a parsed record.
+    pub fn at(record: &StringRecord, err: impl std::fmt::Display) -> Self {
+        Self::new(record.position(), err.to_string())
+    }
+
+    fn new(position: Option<&Position>, message: String) -> Self {
+        Self {
+            line: position.map_or(0, Position::line),
+            byte: position.map_or(0, Position::byte),
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csv::ReaderBuilder;
+
+    fn first_error(input: &str) -> RowError {
+        let mut reader = ReaderBuilder::new().from_reader(input.as_bytes());
+        let err = reader.records().find_map(|record| record.err()).unwrap();
+        RowError::from_csv(err)
+    }
+
+    #[test]
+    fn test_policies() {
+        let input = "a,b\n1,2\n3,4,5\n";
+        let dialect = Dialect::default();
+
+        let err = OnError::Fail
+            .handle(first_error(input), input.as_bytes(), &dialect)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "line 3, byte 8: found 3 fields, expected 2");
+
+        let skipped = OnError::Skip
+            .handle(first_error(input), input.as_bytes(), &dialect)
+            .unwrap();
+        assert_eq!(skipped, None);
+
+        let emitted = OnError::Emit
+            .handle(first_error(input), input.as_bytes(), &dialect)
+            .unwrap();
+        assert_eq!(
+            emitted,
+            Some(json!({
+                "error": "found 3 fields, expected 2",
+                "line": 3,
+                "byte": 8,
+                "raw": "3,4,5",
+            }))
+        );
+    }
+}
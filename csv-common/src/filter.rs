@@ -0,0 +1,332 @@
+use csv::StringRecord;
+use fluvio_smartmodule::{eyre, Result};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Which CSV columns are converted into JSON fields.
+#[derive(Debug, Default)]
+pub enum Projection {
+    #[default]
+    All,
+    /// Only the listed columns.
+    Include(Vec<String>),
+    /// All columns except the listed ones.
+    Exclude(Vec<String>),
+}
+
+impl Projection {
+    /// Build the projection from the `include_columns` and `exclude_columns` params.
+    pub fn new(include: Option<Vec<String>>, exclude: Option<Vec<String>>) -> Result<Self> {
+        match (include, exclude) {
+            (Some(_), Some(_)) => Err(eyre!(
+                "`include_columns` and `exclude_columns` cannot be set together"
+            )),
+            (Some(columns), None) => Ok(Projection::Include(columns)),
+            (None, Some(columns)) => Ok(Projection::Exclude(columns)),
+            (None, None) => Ok(Projection::All),
+        }
+    }
+
+    /// Whether the column with the given CSV header is converted.
+    pub fn selects(&self, header: &str) -> bool {
+        match self {
+            Projection::All => true,
+            Projection::Include(columns) => columns.iter().any(|column| column == header),
+            Projection::Exclude(columns) => !columns.iter().any(|column| column == header),
+        }
+    }
+
+    /// Fail if an included column is not in the header.
+    pub fn check(&self, headers: &[&str]) -> Result<()> {
+        if let Projection::Include(columns) = self {
+            if let Some(column) = columns
+                .iter()
+                .find(|column| !headers.contains(&column.as_str()))
+            {
+                return Err(eyre!(
+                    "included column `{}` is missing from the header",
+                    column
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Row predicates; a row is converted only when all of them match.
+#[derive(Debug, Default)]
+pub struct RowFilter {
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug)]
+struct Predicate {
+    column: String,
+    condition: Condition,
+}
+
+#[derive(Debug)]
+enum Condition {
+    Equals(String),
+    NotEquals(String),
+    Matches(Regex),
+    Number(NumberOp, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// JSON form of a predicate in the `filter` param.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PredicateSpec {
+    column: String,
+    op: Op,
+    value: Value,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Op {
+    Eq,
+    Ne,
+    Regex,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl RowFilter {
+    /// Parse the JSON `filter` param: a predicate or a list of predicates.
+    pub fn parse(raw: &str) -> Result<Self> {
+        let parse_err = |err: serde_json::Error| eyre!("cannot parse `filter` param: {}", err);
+        let specs: Vec<PredicateSpec> = match serde_json::from_str(raw).map_err(parse_err)? {
+            Value::Array(items) => items
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<serde_json::Result<_>>()
+                .map_err(parse_err)?,
+            value => vec![serde_json::from_value(value).map_err(parse_err)?],
+        };
+
+        let predicates = specs
+            .into_iter()
+            .map(Predicate::from_spec)
+            .collect::<Result<Vec<Predicate>>>()?;
+
+        Ok(Self { predicates })
+    }
+
+    /// Resolve the predicates against the header into `(position, predicate)` pairs.
+    pub fn resolve(&self, headers: &[&str]) -> Result<Vec<RowPredicate<'_>>> {
+        self.predicates
+            .iter()
+            .map(|predicate| {
+                headers
+                    .iter()
+                    .position(|header| *header == predicate.column)
+                    .map(|position| RowPredicate {
+                        position,
+                        predicate,
+                    })
+                    .ok_or_else(|| {
+                        eyre!(
+                            "filter column `{}` is missing from the header",
+                            predicate.column
+                        )
+                    })
+            })
+            .collect()
+    }
+}
+
+/// A predicate bound to the position of its column in the CSV row.
+pub struct RowPredicate<'a> {
+    position: usize,
+    predicate: &'a Predicate,
+}
+
+impl RowPredicate<'_> {
+    /// Evaluate the predicate on the raw cell; a missing cell is empty.
+    pub fn matches(&self, record: &StringRecord) -> bool {
+        let cell = record.get(self.position).unwrap_or_default();
+        match &self.predicate.condition {
+            Condition::Equals(value) => cell == value,
+            Condition::NotEquals(value) => cell != value,
+            Condition::Matches(regex) => regex.is_match(cell),
+            Condition::Number(op, value) => match cell.parse::<f64>() {
+                Ok(number) => op.apply(number, *value),
+                // A cell that is not a number only differs from one
+                Err(_) => *op == NumberOp::Ne,
+            },
+        }
+    }
+}
+
+impl Predicate {
+    fn from_spec(spec: PredicateSpec) -> Result<Self> {
+        let column = spec.column;
+        let invalid = |expected: &str| {
+            eyre!(
+                "invalid filter on `{}`: expected {} value",
+                column,
+                expected
+            )
+        };
+        // Numeric comparisons also accept the number as a string
+        let threshold = |value: Value| {
+            let number = match value {
+                Value::Number(n) => n.as_f64(),
+                Value::String(s) => s.trim().parse().ok(),
+                _ => None,
+            };
+            number.ok_or_else(|| invalid("a numeric"))
+        };
+
+        let condition = match (spec.op, spec.value) {
+            (Op::Eq, Value::String(value)) => Condition::Equals(value),
+            (Op::Ne, Value::String(value)) => Condition::NotEquals(value),
+            (Op::Regex, Value::String(pattern)) => Condition::Matches(
+                Regex::new(&pattern)
+                    .map_err(|err| eyre!("invalid filter regex on `{}`: {}", column, err))?,
+            ),
+            (Op::Regex, _) => return Err(invalid("a string")),
+            (Op::Eq | Op::Ne, Value::Number(n)) => {
+                let op = if matches!(spec.op, Op::Eq) {
+                    NumberOp::Eq
+                } else {
+                    NumberOp::Ne
+                };
+                Condition::Number(op, n.as_f64().ok_or_else(|| invalid("a numeric"))?)
+            }
+            (Op::Eq | Op::Ne, _) => return Err(invalid("a string or numeric")),
+            (Op::Gt, value) => Condition::Number(NumberOp::Gt, threshold(value)?),
+            (Op::Ge, value) => Condition::Number(NumberOp::Ge, threshold(value)?),
+            (Op::Lt, value) => Condition::Number(NumberOp::Lt, threshold(value)?),
+            (Op::Le, value) => Condition::Number(NumberOp::Le, threshold(value)?),
+        };
+
+        Ok(Self { column, condition })
+    }
+}
+
+impl NumberOp {
+    fn apply(self, left: f64, right: f64) -> bool {
+        match self {
+            NumberOp::Eq => left == right,
+            NumberOp::Ne => left != right,
+            NumberOp::Gt => left > right,
+            NumberOp::Ge => left >= right,
+            NumberOp::Lt => left < right,
+            NumberOp::Le => left <= right,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matching_rows(filter: &str, headers: &[&str], rows: &[&[&str]]) -> Vec<usize> {
+        let filter = RowFilter::parse(filter).unwrap();
+        let predicates = filter.resolve(headers).unwrap();
+        rows.iter()
+            .enumerate()
+            .filter(|(_, row)| {
+                let record = StringRecord::from(row.to_vec());
+                predicates
+                    .iter()
+                    .all(|predicate| predicate.matches(&record))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    #[test]
+    fn test_text_predicates() {
+        let headers = ["status", "name"];
+        let rows: &[&[&str]] = &[
+            &["active", "Alice"],
+            &["inactive", "Bob"],
+            &["active", "Carl"],
+        ];
+
+        let eq = r#"{"column": "status", "op": "eq", "value": "active"}"#;
+        assert_eq!(matching_rows(eq, &headers, rows), vec![0, 2]);
+
+        let ne = r#"{"column": "status", "op": "ne", "value": "active"}"#;
+        assert_eq!(matching_rows(ne, &headers, rows), vec![1]);
+
+        let regex = r#"{"column": "name", "op": "regex", "value": "^(A|B)"}"#;
+        assert_eq!(matching_rows(regex, &headers, rows), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_numeric_predicates() {
+        let headers = ["amount"];
+        let rows: &[&[&str]] = &[&["5"], &["10.0"], &["12.5"], &["n/a"], &[]];
+
+        let gt = r#"{"column": "amount", "op": "gt", "value": 5}"#;
+        assert_eq!(matching_rows(gt, &headers, rows), vec![1, 2]);
+
+        let le = r#"{"column": "amount", "op": "le", "value": "10"}"#;
+        assert_eq!(matching_rows(le, &headers, rows), vec![0, 1]);
+
+        let eq = r#"{"column": "amount", "op": "eq", "value": 10}"#;
+        assert_eq!(matching_rows(eq, &headers, rows), vec![1]);
+
+        let ne = r#"{"column": "amount", "op": "ne", "value": 10}"#;
+        assert_eq!(matching_rows(ne, &headers, rows), vec![0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_all_predicates_must_match() {
+        let headers = ["status", "amount"];
+        let rows: &[&[&str]] = &[&["active", "3"], &["active", "30"], &["closed", "30"]];
+
+        let filter = r#"[
+            {"column": "status", "op": "eq", "value": "active"},
+            {"column": "amount", "op": "ge", "value": 10}
+        ]"#;
+        assert_eq!(matching_rows(filter, &headers, rows), vec![1]);
+    }
+
+    #[test]
+    fn test_rejects_invalid_filters() {
+        assert!(RowFilter::parse(r#"{"column": "a", "op": "like", "value": "x"}"#).is_err());
+        assert!(RowFilter::parse(r#"{"column": "a", "op": "gt", "value": "many"}"#).is_err());
+        assert!(RowFilter::parse(r#"{"column": "a", "op": "regex", "value": "("}"#).is_err());
+        assert!(RowFilter::parse(r#"{"column": "a", "op": "eq", "value": null}"#).is_err());
+
+        let filter = RowFilter::parse(r#"{"column": "a", "op": "eq", "value": "x"}"#).unwrap();
+        let err = filter.resolve(&["b"]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "filter column `a` is missing from the header"
+        );
+    }
+
+    #[test]
+    fn test_projection() {
+        assert!(Projection::new(Some(vec![]), Some(vec![])).is_err());
+
+        let include = Projection::Include(vec!["a".to_string()]);
+        assert!(include.selects("a"));
+        assert!(!include.selects("b"));
+        assert!(include.check(&["a", "b"]).is_ok());
+        assert!(include.check(&["b"]).is_err());
+
+        let exclude = Projection::Exclude(vec!["a".to_string()]);
+        assert!(!exclude.selects("a"));
+        assert!(exclude.selects("b"));
+    }
+}
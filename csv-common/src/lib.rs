@@ -0,0 +1,17 @@
+//! Building blocks shared by the SmartModules that turn CSV text into JSON,
+//! `csv-json-records` and `csv-json-array`, and by `json-csv` for the other direction.
+//! `fixed-width-json-records` and `excel-json-records` reuse the header case and the
+//! error policy.
+
+pub mod case;
+pub mod datetime;
+pub mod dialect;
+pub mod encoding;
+pub mod error;
+pub mod filter;
+pub mod params;
+pub mod row;
+pub mod schema;
+pub mod sniff;
+pub mod typing;
+pub mod unflatten;
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+
+use csv::StringRecord;
+use fluvio_smartmodule::{dataplane::smartmodule::SmartModuleExtraParams, eyre, Result};
+use serde_json::Value;
+
+use crate::case::HeaderCase;
+use crate::dialect::Dialect;
+use crate::encoding::InputEncoding;
+use crate::error::OnError;
+use crate::filter::{Projection, RowFilter};
+use crate::schema::Schema;
+use crate::sniff::Sniffed;
+
+/// Delimiter used when none is configured.
+pub const DEFAULT_DELIMITER: u8 = b',';
+/// Field collecting the extra cells of long rows when none is configured.
+pub const DEFAULT_EXTRA_FIELD: &str = "_extra";
+/// Separator between the keys of a nested field path when none is configured.
+pub const DEFAULT_PATH_SEPARATOR: &str = ".";
+
+const DELIMITER_PARAM_NAME: &str = "delimiter";
+const DIALECT_PARAM_NAME: &str = "dialect";
+const ENCODING_PARAM_NAME: &str = "encoding";
+const HEADER_CASE_PARAM_NAME: &str = "header_case";
+const HEADER_MAP_PARAM_NAME: &str = "header_map";
+const HEADERS_PARAM_NAME: &str = "headers";
+const INFER_TYPES_PARAM_NAME: &str = "infer_types";
+const SCHEMA_PARAM_NAME: &str = "schema";
+const ON_ERROR_PARAM_NAME: &str = "on_error";
+const RAGGED_ROWS_PARAM_NAME: &str = "ragged_rows";
+const MISSING_VALUE_PARAM_NAME: &str = "missing_value";
+const EXTRA_FIELD_PARAM_NAME: &str = "extra_field";
+const UNFLATTEN_PARAM_NAME: &str = "unflatten";
+const PATH_SEPARATOR_PARAM_NAME: &str = "path_separator";
+const INCLUDE_COLUMNS_PARAM_NAME: &str = "include_columns";
+const EXCLUDE_COLUMNS_PARAM_NAME: &str = "exclude_columns";
+const FILTER_PARAM_NAME: &str = "filter";
+
+/// Params controlling how CSV input is read and converted into JSON.
+#[derive(Debug)]
+pub struct CsvParams {
+    pub dialect: Dialect,
+    pub encoding: InputEncoding,
+    pub header_case: HeaderCase,
+    pub header_map: HashMap<String, String>,
+    pub headers: Headers,
+    pub infer_types: bool,
+    pub schema: Schema,
+    pub on_error: OnError,
+    pub ragged_rows: RaggedRows,
+    pub missing_value: Value,
+    pub extra_field: String,
+    pub unflatten: bool,
+    pub path_separator: String,
+    pub projection: Projection,
+    pub filter: RowFilter,
+}
+
+impl CsvParams {
+    pub fn new(delimiter: u8, header_case: HeaderCase) -> Self {
+        Self {
+            dialect: Dialect::with_delimiter(delimiter),
+            header_case,
+            ..Self::default()
+        }
+    }
+
+    /// Read the CSV params from the SmartModule params.
+    pub fn from_params(params: &SmartModuleExtraParams) -> Result<Self> {
+        let dialect_param = Dialect::from_params(
+            params.get(DIALECT_PARAM_NAME).map(String::as_str),
+            params.get(DELIMITER_PARAM_NAME).map(String::as_str),
+        )?;
+
+        let encoding_param = match params.get(ENCODING_PARAM_NAME) {
+            Some(v) => InputEncoding::parse(v)?,
+            None => InputEncoding::Utf8,
+        };
+
+        let case_param = match params.get(HEADER_CASE_PARAM_NAME) {
+            Some(v) => HeaderCase::try_from(v.to_string())
+                .map_err(|e| eyre!("failed to parse header_case: {}", e))?,
+            None => HeaderCase::None,
+        };
+
+        let header_map_param = match params.get(HEADER_MAP_PARAM_NAME) {
+            Some(v) => parse_header_map(v)?,
+            None => HashMap::new(),
+        };
+
+        let headers_param = match params.get(HEADERS_PARAM_NAME) {
+            Some(v) => Headers::parse(v)?,
+            None => Headers::FirstRow,
+        };
+
+        let infer_types_param = match params.get(INFER_TYPES_PARAM_NAME) {
+            Some(v) => parse_bool(INFER_TYPES_PARAM_NAME, v)?,
+            None => false,
+        };
+
+        let schema_param = match params.get(SCHEMA_PARAM_NAME) {
+            Some(v) => Schema::parse(v)?,
+            None => Schema::default(),
+        };
+
+        let on_error_param = match params.get(ON_ERROR_PARAM_NAME) {
+            Some(v) => OnError::try_from(v.to_string())
+                .map_err(|e| eyre!("failed to parse on_error: {}", e))?,
+            None => OnError::Fail,
+        };
+
+        let ragged_rows_param = match params.get(RAGGED_ROWS_PARAM_NAME) {
+            Some(v) => RaggedRows::try_from(v.to_string())
+                .map_err(|e| eyre!("failed to parse ragged_rows: {}", e))?,
+            None => RaggedRows::Fill,
+        };
+
+        // Plain text that is not a JSON literal is used as a string
+        let missing_value_param = params.get(MISSING_VALUE_PARAM_NAME).map_or(Value::Null, |v| {
+            serde_json::from_str(v).unwrap_or_else(|_| Value::String(v.to_string()))
+        });
+
+        let extra_field_param = params
+            .get(EXTRA_FIELD_PARAM_NAME)
+            .map_or(DEFAULT_EXTRA_FIELD.to_string(), |v| v.to_string());
+
+        let unflatten_param = match params.get(UNFLATTEN_PARAM_NAME) {
+            Some(v) => parse_bool(UNFLATTEN_PARAM_NAME, v)?,
+            None => false,
+        };
+
+        let path_separator_param = match params.get(PATH_SEPARATOR_PARAM_NAME) {
+            Some(v) if v.is_empty() => return Err(eyre!("`path_separator` param cannot be empty")),
+            Some(v) => v.to_string(),
+            None => DEFAULT_PATH_SEPARATOR.to_string(),
+        };
+
+        let projection_param = Projection::new(
+            params
+                .get(INCLUDE_COLUMNS_PARAM_NAME)
+                .map(|v| parse_list(INCLUDE_COLUMNS_PARAM_NAME, v))
+                .transpose()?,
+            params
+                .get(EXCLUDE_COLUMNS_PARAM_NAME)
+                .map(|v| parse_list(EXCLUDE_COLUMNS_PARAM_NAME, v))
+                .transpose()?,
+        )?;
+
+        let filter_param = match params.get(FILTER_PARAM_NAME) {
+            Some(v) => RowFilter::parse(v)?,
+            None => RowFilter::default(),
+        };
+
+        Ok(Self {
+            dialect: dialect_param,
+            encoding: encoding_param,
+            header_case: case_param,
+            header_map: header_map_param,
+            headers: headers_param,
+            infer_types: infer_types_param,
+            schema: schema_param,
+            on_error: on_error_param,
+            ragged_rows: ragged_rows_param,
+            missing_value: missing_value_param,
+            extra_field: extra_field_param,
+            unflatten: unflatten_param,
+            path_separator: path_separator_param,
+            projection: projection_param,
+            filter: filter_param,
+        })
+    }
+
+    /// Whether each record is sniffed before it is read.
+    pub fn sniffs(&self) -> bool {
+        self.dialect.auto_delimiter || matches!(self.headers, Headers::Detect)
+    }
+}
+
+impl Default for CsvParams {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::default(),
+            encoding: InputEncoding::Utf8,
+            header_case: HeaderCase::None,
+            header_map: HashMap::new(),
+            headers: Headers::FirstRow,
+            infer_types: false,
+            schema: Schema::default(),
+            on_error: OnError::Fail,
+            ragged_rows: RaggedRows::Fill,
+            missing_value: Value::Null,
+            extra_field: DEFAULT_EXTRA_FIELD.to_string(),
+            unflatten: false,
+            path_separator: DEFAULT_PATH_SEPARATOR.to_string(),
+            projection: Projection::All,
+            filter: RowFilter::default(),
+        }
+    }
+}
+
+/// Where the column names come from.
+#[derive(Debug)]
+pub enum Headers {
+    /// The first line of the CSV input.
+    FirstRow,
+    /// The first line when it is sniffed as a header, generated names otherwise.
+    Detect,
+    /// Generated `col_0..col_n` names; the first line is data.
+    Auto,
+    /// User-supplied names; the first line is data.
+    Names(Vec<String>),
+}
+
+impl Headers {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim() {
+            "" => Ok(Headers::FirstRow),
+            v if v.eq_ignore_ascii_case("auto") => Ok(Headers::Auto),
+            v if v.eq_ignore_ascii_case("detect") => Ok(Headers::Detect),
+            v => Ok(Headers::Names(parse_list(HEADERS_PARAM_NAME, v)?)),
+        }
+    }
+
+    /// Whether the first line holds the names; `detect` follows the sniffed dialect.
+    pub fn in_first_row(&self, sniffed: Option<&Sniffed>) -> bool {
+        match self {
+            Headers::FirstRow => true,
+            Headers::Detect => sniffed.is_none_or(|sniffed| sniffed.has_header),
+            Headers::Auto | Headers::Names(_) => false,
+        }
+    }
+
+    /// Resolve the header names, given the first record of the CSV input.
+    pub fn resolve(&self, first_record: &StringRecord, sniffed: Option<&Sniffed>) -> StringRecord {
+        match self {
+            Headers::FirstRow => first_record.clone(),
+            Headers::Detect if self.in_first_row(sniffed) => first_record.clone(),
+            Headers::Auto | Headers::Detect => {
+                (0..first_record.len()).map(|i| format!("col_{}", i)).collect()
+            }
+            Headers::Names(names) => StringRecord::from(names.clone()),
+        }
+    }
+}
+
+/// How rows with fewer or more fields than the header are handled.
+#[derive(Debug, Default)]
+pub enum RaggedRows {
+    /// Fill missing columns and collect extra cells.
+    #[default]
+    Fill,
+    /// Reject the row through the error policy.
+    Strict,
+}
+
+impl TryFrom<String> for RaggedRows {
+    type Error = &'static str;
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "" | "fill" => Ok(RaggedRows::Fill),
+            "strict" => Ok(RaggedRows::Strict),
+            _ => Err("Invalid ragged rows mode"),
+        }
+    }
+}
+
+/// Parse a `true`/`false` param value.
+pub fn parse_bool(name: &str, value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(eyre!("invalid `{}` param: expected `true` or `false`, got `{}`", name, value)),
+    }
+}
+
+/// Parse the `header_map` param, a JSON object from CSV header names to field names.
+pub fn parse_header_map(value: &str) -> Result<HashMap<String, String>> {
+    let header_map: HashMap<String, String> = serde_json::from_str(value)
+        .map_err(|err| eyre!("cannot parse `header_map` param: {}", err))?;
+    if let Some((header, _)) = header_map.iter().find(|(_, name)| name.is_empty()) {
+        return Err(eyre!("header_map renames `{}` to an empty name", header));
+    }
+    Ok(header_map)
+}
+
+/// Parse a list param, given either as a JSON array or as comma-separated values.
+pub fn parse_list(name: &str, value: &str) -> Result<Vec<String>> {
+    let list: Vec<String> = if value.trim_start().starts_with('[') {
+        serde_json::from_str(value).map_err(|err| eyre!("cannot parse `{}` param: {}", name, err))?
+    } else {
+        value.split(',').map(|item| item.trim().to_string()).collect()
+    };
+    if list.is_empty() || list.iter().any(String::is_empty) {
+        return Err(eyre!("invalid `{}` param: empty name in `{}`", name, value));
+    }
+    Ok(list)
+}
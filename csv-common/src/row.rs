@@ -0,0 +1,252 @@
+use std::collections::HashSet;
+
+use csv::StringRecord;
+use fluvio_smartmodule::{eyre, Result};
+use serde_json::{Map, Value};
+
+use crate::error::RowError;
+use crate::filter::RowPredicate;
+use crate::params::{CsvParams, RaggedRows};
+use crate::case::HeaderCase;
+use crate::schema::ColumnSpec;
+use crate::typing;
+use crate::unflatten::{self, display_path, Segment};
+
+/// Output columns resolved from the CSV header, the header case, the schema and the
+/// column projection, along with the row filter.
+pub struct RowLayout<'a> {
+    columns: Vec<Column<'a>>,
+    defaults: Vec<(Vec<Segment>, Value)>,
+    predicates: Vec<RowPredicate<'a>>,
+    path_separator: &'a str,
+}
+
+struct Column<'a> {
+    path: Vec<Segment>,
+    spec: Option<&'a ColumnSpec>,
+    selected: bool,
+}
+
+impl<'a> RowLayout<'a> {
+    pub fn new(headers: &StringRecord, params: &'a CsvParams) -> Result<Self> {
+        let header_names: Vec<&str> = headers.iter().collect();
+        params.projection.check(&header_names)?;
+        let predicates = params.filter.resolve(&header_names)?;
+
+        let mut columns = header_names
+            .iter()
+            .map(|header| {
+                let spec = params.schema.column(header);
+                let path = column_path(header, spec, params);
+                let selected = params.projection.selects(header);
+                Column {
+                    path,
+                    spec,
+                    selected,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Absent schema columns with a default are still emitted
+        let mut defaults = params
+            .schema
+            .absent_columns(&header_names)?
+            .into_iter()
+            .filter(|spec| params.projection.selects(spec.column()))
+            .filter_map(|spec| {
+                let path = column_path(spec.column(), Some(spec), params);
+                spec.default_value().map(|default| (path, default.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        disambiguate(
+            columns
+                .iter_mut()
+                .filter(|column| column.selected)
+                .map(|column| &mut column.path)
+                .chain(defaults.iter_mut().map(|(path, _)| path)),
+            &params.path_separator,
+        );
+
+        // Extra cells would overwrite a column named like the extra field
+        if let RaggedRows::Fill = params.ragged_rows {
+            let collides = columns
+                .iter()
+                .filter(|column| column.selected)
+                .map(|column| &column.path)
+                .chain(defaults.iter().map(|(path, _)| path))
+                .any(|path| {
+                    matches!(path.first(), Some(Segment::Key(key)) if *key == params.extra_field)
+                });
+            if collides {
+                return Err(eyre!(
+                    "extra field `{}` collides with a column",
+                    params.extra_field
+                ));
+            }
+        }
+
+        if params.unflatten {
+            let paths = columns
+                .iter()
+                .filter(|column| column.selected)
+                .map(|column| column.path.as_slice())
+                .chain(defaults.iter().map(|(path, _)| path.as_slice()));
+            unflatten::check_conflicts(paths, &params.path_separator)?;
+        }
+
+        Ok(Self {
+            columns,
+            defaults,
+            predicates,
+            path_separator: &params.path_separator,
+        })
+    }
+
+    /// Top-level output field names, in header order, followed by the schema defaults.
+    pub fn field_names(&self) -> Vec<String> {
+        let paths = self
+            .columns
+            .iter()
+            .filter(|column| column.selected)
+            .map(|column| &column.path)
+            .chain(self.defaults.iter().map(|(path, _)| path));
+
+        let mut names: Vec<String> = Vec::new();
+        for path in paths {
+            if let Some(Segment::Key(name)) = path.first() {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        }
+        names
+    }
+
+    /// Path of the output field with the given name, written the way it would appear in a
+    /// header when unflattening, such as `address.city`.
+    pub fn field_path(&self, name: &str) -> Option<&[Segment]> {
+        self.columns
+            .iter()
+            .filter(|column| column.selected)
+            .map(|column| column.path.as_slice())
+            .chain(self.defaults.iter().map(|(path, _)| path.as_slice()))
+            .find(|path| display_path(path, self.path_separator) == name)
+    }
+
+    /// Convert a row read by the CSV reader into a JSON object, or `None` if the row
+    /// filter rejects it.
+    pub fn convert(
+        &self,
+        result: csv::Result<StringRecord>,
+        params: &CsvParams,
+    ) -> std::result::Result<Option<Value>, RowError> {
+        let record = result.map_err(RowError::from_csv)?;
+        if !self.matches(&record) {
+            return Ok(None);
+        }
+        self.row_to_json(&record, params)
+            .map(Some)
+            .map_err(|err| RowError::at(&record, err))
+    }
+
+    /// Whether the raw CSV row passes the row filter.
+    pub fn matches(&self, record: &StringRecord) -> bool {
+        self.predicates.iter().all(|predicate| predicate.matches(record))
+    }
+
+    /// Convert a CSV row into a JSON object.
+    ///
+    /// Short rows get the missing trailing columns filled and long rows get the extra
+    /// cells collected under the extra field, unless ragged rows are rejected.
+    pub fn row_to_json(&self, record: &StringRecord, params: &CsvParams) -> Result<Value> {
+        if let RaggedRows::Strict = params.ragged_rows {
+            if record.len() != self.columns.len() {
+                return Err(eyre!(
+                    "found {} fields, expected {}",
+                    record.len(),
+                    self.columns.len()
+                ));
+            }
+        }
+
+        let mut json_object = Map::new();
+
+        for (index, column) in self.columns.iter().enumerate() {
+            if !column.selected {
+                continue;
+            }
+            let value = match (record.get(index), column.spec) {
+                (Some(cell), Some(spec)) => spec.convert(cell, params.infer_types)?,
+                (Some(cell), None) => typing::cell_to_json(cell, params.infer_types),
+                (None, Some(spec)) => spec.fill_missing(&params.missing_value)?,
+                (None, None) => params.missing_value.clone(),
+            };
+            unflatten::insert_path(&mut json_object, &column.path, value);
+        }
+
+        if record.len() > self.columns.len() {
+            let extra = record
+                .iter()
+                .skip(self.columns.len())
+                .map(|cell| typing::cell_to_json(cell, params.infer_types))
+                .collect();
+            json_object.insert(params.extra_field.clone(), Value::Array(extra));
+        }
+
+        for (path, default) in &self.defaults {
+            unflatten::insert_path(&mut json_object, path, default.clone());
+        }
+
+        Ok(Value::Object(json_object))
+    }
+}
+
+/// Output path of a column: the schema rename, else the `header_map` entry, else the
+/// cased header.
+fn column_path(header: &str, spec: Option<&ColumnSpec>, params: &CsvParams) -> Vec<Segment> {
+    let rename = spec
+        .and_then(ColumnSpec::rename)
+        .or_else(|| params.header_map.get(header).map(String::as_str));
+    match rename {
+        Some(name) => field_path(name, None, params),
+        None => field_path(header, Some(&params.header_case), params),
+    }
+}
+
+/// Give duplicate or empty field names a `_1`, `_2`, ... suffix, in column order.
+fn disambiguate<'p>(paths: impl IntoIterator<Item = &'p mut Vec<Segment>>, separator: &str) {
+    let mut seen = HashSet::new();
+    for path in paths {
+        if let Some(Segment::Key(base)) = path.last().cloned() {
+            let mut suffix = 0;
+            while (suffix == 0 && base.is_empty())
+                || seen.contains(&display_path(path, separator))
+            {
+                suffix += 1;
+                if let Some(last) = path.last_mut() {
+                    *last = Segment::Key(format!("{}_{}", base, suffix));
+                }
+            }
+        }
+        seen.insert(display_path(path, separator));
+    }
+}
+
+/// Output path of a field, split into nested segments when unflattening.
+///
+/// The header case is applied to each key, so separators survive the case transformation.
+fn field_path(name: &str, header_case: Option<&HeaderCase>, params: &CsvParams) -> Vec<Segment> {
+    let apply_case = |key: &str| header_case.map_or_else(|| key.to_string(), |case| case.apply(key));
+
+    if !params.unflatten {
+        return vec![Segment::Key(apply_case(name))];
+    }
+    unflatten::parse_path(name, &params.path_separator)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Key(key) => Segment::Key(apply_case(&key)),
+            index => index,
+        })
+        .collect()
+}
@@ -0,0 +1,295 @@
+use std::collections::HashSet;
+
+use fluvio_smartmodule::{eyre, Result};
+use serde::Deserialize;
+use serde_json::{Number, Value};
+
+use crate::datetime::DatetimeSpec;
+use crate::typing;
+
+/// Per-column contract applied while converting CSV rows into JSON.
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Schema {
+    columns: Vec<ColumnSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnSpec {
+    /// Column name as it appears in the CSV header.
+    column: String,
+    /// Target type; when omitted the column follows the `infer_types` setting.
+    #[serde(rename = "type", default)]
+    column_type: Option<ColumnType>,
+    /// Output field name; when omitted the cased header name is used.
+    #[serde(default)]
+    rename: Option<String>,
+    /// Value used when the cell is empty or the column is absent.
+    #[serde(default)]
+    default: Option<Value>,
+    /// Reject rows where the value is missing and there is no default.
+    #[serde(default)]
+    required: bool,
+    /// Parse the column as a date/time and normalize it.
+    #[serde(default)]
+    datetime: Option<DatetimeSpec>,
+}
+
+/// Target type of a column.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+impl Schema {
+    /// Parse and validate the JSON `schema` param.
+    pub fn parse(raw: &str) -> Result<Self> {
+        let schema: Schema = serde_json::from_str(raw)
+            .map_err(|err| eyre!("cannot parse `schema` param: {}", err))?;
+
+        let mut seen = HashSet::new();
+        for spec in &schema.columns {
+            if !seen.insert(spec.column.as_str()) {
+                return Err(eyre!("schema lists column `{}` more than once", spec.column));
+            }
+            if spec.rename.as_deref() == Some("") {
+                return Err(eyre!("schema column `{}` has an empty rename", spec.column));
+            }
+            if spec.datetime.is_some() && spec.column_type.is_some() {
+                return Err(eyre!(
+                    "schema column `{}` cannot have both `type` and `datetime`",
+                    spec.column
+                ));
+            }
+        }
+
+        Ok(schema)
+    }
+
+    /// Look up the spec for a CSV header.
+    pub fn column(&self, header: &str) -> Option<&ColumnSpec> {
+        self.columns.iter().find(|spec| spec.column == header)
+    }
+
+    /// Check the header against the schema and return the specs of absent columns.
+    ///
+    /// Fails if a required column without a default is not in the header.
+    pub fn absent_columns<'a>(&'a self, headers: &[&str]) -> Result<Vec<&'a ColumnSpec>> {
+        let mut absent = Vec::new();
+        for spec in &self.columns {
+            if headers.contains(&spec.column.as_str()) {
+                continue;
+            }
+            if spec.required && spec.default.is_none() {
+                return Err(eyre!("required column `{}` is missing from the header", spec.column));
+            }
+            absent.push(spec);
+        }
+        Ok(absent)
+    }
+}
+
+impl ColumnSpec {
+    /// Column name as it appears in the CSV header.
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// Explicit output field name, if any.
+    pub fn rename(&self) -> Option<&str> {
+        self.rename.as_deref()
+    }
+
+    /// Default value of the column, if any.
+    pub fn default_value(&self) -> Option<&Value> {
+        self.default.as_ref()
+    }
+
+    /// Value of a trailing cell missing from a short row.
+    pub fn fill_missing(&self, missing_value: &Value) -> Result<Value> {
+        match (&self.default, self.required) {
+            (Some(default), _) => Ok(default.clone()),
+            (None, true) => Err(eyre!("column `{}` is required but missing", self.column)),
+            (None, false) => Ok(missing_value.clone()),
+        }
+    }
+
+    /// Convert a cell according to the spec, filling defaults and enforcing `required`.
+    ///
+    /// An empty cell without a default becomes `null` for the non-string types and
+    /// `datetime` columns and otherwise converts like any other cell, so `string` columns
+    /// keep `""`.
+    pub fn convert(&self, cell: &str, infer_types: bool) -> Result<Value> {
+        if cell.is_empty() {
+            if let Some(default) = &self.default {
+                return Ok(default.clone());
+            }
+            if self.required {
+                return Err(eyre!("column `{}` is required but empty", self.column));
+            }
+        }
+
+        if let Some(datetime) = &self.datetime {
+            if cell.is_empty() {
+                return Ok(Value::Null);
+            }
+            return datetime
+                .convert(cell)
+                .map_err(|err| eyre!("column `{}`: {}", self.column, err));
+        }
+
+        match self.column_type {
+            Some(ColumnType::String) => Ok(Value::String(cell.to_string())),
+            Some(_) if cell.is_empty() => Ok(Value::Null),
+            Some(column_type) => column_type.cast(cell).ok_or_else(|| {
+                eyre!(
+                    "column `{}`: cannot convert `{}` to {}",
+                    self.column,
+                    cell,
+                    column_type.name()
+                )
+            }),
+            None => Ok(typing::cell_to_json(cell, infer_types)),
+        }
+    }
+}
+
+impl ColumnType {
+    /// Name of the type, as written in the param.
+    pub fn name(self) -> &'static str {
+        match self {
+            ColumnType::String => "string",
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "float",
+            ColumnType::Boolean => "boolean",
+        }
+    }
+
+    /// Convert a non-empty cell, or `None` when it is not a valid value of the type.
+    pub fn cast(self, cell: &str) -> Option<Value> {
+        match self {
+            ColumnType::String => Some(Value::String(cell.to_string())),
+            ColumnType::Integer => cell
+                .parse::<i64>()
+                .map(Value::from)
+                .or_else(|_| cell.parse::<u64>().map(Value::from))
+                .ok(),
+            ColumnType::Float => cell
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number),
+            ColumnType::Boolean => match cell.to_lowercase().as_str() {
+                "true" | "yes" | "1" => Some(Value::Bool(true)),
+                "false" | "no" | "0" => Some(Value::Bool(false)),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_rejects_duplicate_columns() {
+        let raw = r#"[{"column": "id"}, {"column": "id", "type": "integer"}]"#;
+        let err = Schema::parse(raw).unwrap_err();
+        assert_eq!(err.to_string(), "schema lists column `id` more than once");
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_type() {
+        let raw = r#"[{"column": "id", "type": "decimal"}]"#;
+        assert!(Schema::parse(raw).is_err());
+    }
+
+    #[test]
+    fn test_convert_casts_and_fills() {
+        let raw = r#"[
+            {"column": "id", "type": "integer", "required": true},
+            {"column": "rate", "type": "float", "default": 0.0},
+            {"column": "active", "type": "boolean"},
+            {"column": "zip", "type": "string"},
+            {"column": "note"}
+        ]"#;
+        let schema = Schema::parse(raw).unwrap();
+        let id = schema.column("id").unwrap();
+        let rate = schema.column("rate").unwrap();
+        let active = schema.column("active").unwrap();
+        let zip = schema.column("zip").unwrap();
+        let note = schema.column("note").unwrap();
+
+        assert_eq!(id.convert("42", false).unwrap(), json!(42));
+        assert_eq!(rate.convert("1.5", false).unwrap(), json!(1.5));
+        assert_eq!(rate.convert("", false).unwrap(), json!(0.0));
+        assert_eq!(active.convert("Yes", false).unwrap(), json!(true));
+        assert_eq!(active.convert("", false).unwrap(), Value::Null);
+        assert_eq!(zip.convert("02134", true).unwrap(), json!("02134"));
+        assert_eq!(zip.convert("", true).unwrap(), json!(""));
+        assert_eq!(note.convert("", false).unwrap(), json!(""));
+        assert_eq!(note.convert("", true).unwrap(), Value::Null);
+
+        assert_eq!(
+            id.convert("", false).unwrap_err().to_string(),
+            "column `id` is required but empty"
+        );
+        assert_eq!(
+            id.convert("4.2", false).unwrap_err().to_string(),
+            "column `id`: cannot convert `4.2` to integer"
+        );
+    }
+
+    #[test]
+    fn test_convert_datetime() {
+        let raw = r#"[
+            {"column": "created", "datetime": {"formats": ["%m/%d/%Y %H:%M"], "timezone": "-05:00"}}
+        ]"#;
+        let schema = Schema::parse(raw).unwrap();
+        let created = schema.column("created").unwrap();
+
+        assert_eq!(
+            created.convert("03/04/2024 07:00", false).unwrap(),
+            json!("2024-03-04T12:00:00Z")
+        );
+        assert_eq!(created.convert("", false).unwrap(), Value::Null);
+        assert_eq!(
+            created.convert("2024-03-04", false).unwrap_err().to_string(),
+            "column `created`: `2024-03-04` does not match any datetime format"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_type_with_datetime() {
+        let raw = r#"[{"column": "created", "type": "string", "datetime": {"formats": ["rfc3339"]}}]"#;
+        let err = Schema::parse(raw).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "schema column `created` cannot have both `type` and `datetime`"
+        );
+    }
+
+    #[test]
+    fn test_absent_columns() {
+        let raw = r#"[
+            {"column": "id", "required": true},
+            {"column": "region", "default": "eu"},
+            {"column": "note"}
+        ]"#;
+        let schema = Schema::parse(raw).unwrap();
+
+        let absent = schema.absent_columns(&["id"]).unwrap();
+        let names: Vec<&str> = absent.iter().map(|spec| spec.column()).collect();
+        assert_eq!(names, vec!["region", "note"]);
+
+        let err = schema.absent_columns(&["region"]).unwrap_err();
+        assert_eq!(err.to_string(), "required column `id` is missing from the header");
+    }
+}
@@ -0,0 +1,192 @@
+use csv::StringRecord;
+use serde_json::Value;
+
+use crate::dialect::Dialect;
+use crate::typing;
+
+/// Delimiters tried by `delimiter=auto`, in order of preference.
+const CANDIDATE_DELIMITERS: &[u8] = b",;\t|";
+/// Number of leading rows inspected when sniffing a record.
+const SAMPLE_ROWS: usize = 20;
+
+/// Dialect detected from the first rows of a record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sniffed {
+    pub delimiter: u8,
+    pub has_header: bool,
+}
+
+/// Detect the delimiter, when the dialect asks for it, and whether the first row is a header.
+pub fn sniff(input: &[u8], dialect: &Dialect) -> Sniffed {
+    let delimiter = if dialect.auto_delimiter {
+        sniff_delimiter(input, dialect)
+    } else {
+        dialect.delimiter
+    };
+
+    Sniffed {
+        delimiter,
+        has_header: sniff_header(&sample_rows(input, dialect, delimiter)),
+    }
+}
+
+fn sample_rows(input: &[u8], dialect: &Dialect, delimiter: u8) -> Vec<StringRecord> {
+    dialect
+        .reader_builder()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(input)
+        .records()
+        .take(SAMPLE_ROWS)
+        .filter_map(|row| row.ok())
+        .collect()
+}
+
+/// Pick the candidate that splits the sample into the most consistent number of fields.
+///
+/// Candidates producing a single field are ignored; ties go to the earlier candidate,
+/// and the configured delimiter is kept when no candidate splits the sample.
+fn sniff_delimiter(input: &[u8], dialect: &Dialect) -> u8 {
+    let mut best: Option<(f64, usize, u8)> = None;
+
+    for &delimiter in CANDIDATE_DELIMITERS {
+        if !dialect.allows_delimiter(delimiter) {
+            continue;
+        }
+        let rows = sample_rows(input, dialect, delimiter);
+        let Some((fields, count)) = most_common_width(&rows) else {
+            continue;
+        };
+        if fields < 2 {
+            continue;
+        }
+        let consistency = count as f64 / rows.len() as f64;
+        let is_better = best.is_none_or(|(best_consistency, best_fields, _)| {
+            consistency > best_consistency
+                || (consistency == best_consistency && fields > best_fields)
+        });
+        if is_better {
+            best = Some((consistency, fields, delimiter));
+        }
+    }
+
+    best.map_or(dialect.delimiter, |(_, _, delimiter)| delimiter)
+}
+
+/// Most frequent number of fields per row, and how many rows have it.
+fn most_common_width(rows: &[StringRecord]) -> Option<(usize, usize)> {
+    let mut widths: Vec<(usize, usize)> = Vec::new();
+    for row in rows {
+        match widths.iter_mut().find(|(width, _)| *width == row.len()) {
+            Some((_, count)) => *count += 1,
+            None => widths.push((row.len(), 1)),
+        }
+    }
+    widths
+        .into_iter()
+        .max_by(|(a_width, a_count), (b_width, b_count)| {
+            a_count.cmp(b_count).then(a_width.cmp(b_width))
+        })
+}
+
+/// Guess whether the first row is a header by comparing it with the rows below.
+///
+/// Each column whose data cells share a kind votes: a first cell of another kind, or
+/// of another length for fixed-length text, counts for a header, a matching one against.
+/// Ties, including inputs with no data rows, count as a header.
+fn sniff_header(rows: &[StringRecord]) -> bool {
+    let Some((first, data)) = rows.split_first() else {
+        return true;
+    };
+
+    let mut votes = 0i32;
+    for (index, header) in first.iter().enumerate() {
+        let cells: Vec<&str> = data
+            .iter()
+            .filter_map(|row| row.get(index))
+            .filter(|cell| !cell.is_empty())
+            .collect();
+        let Some(kind) = uniform(cells.iter().map(|cell| CellKind::of(cell))) else {
+            continue;
+        };
+
+        let looks_like_data = match kind {
+            CellKind::Text => match uniform(cells.iter().map(|cell| cell.chars().count())) {
+                Some(length) => header.chars().count() == length,
+                None => continue,
+            },
+            kind => CellKind::of(header) == kind,
+        };
+        votes += if looks_like_data { -1 } else { 1 };
+    }
+
+    votes >= 0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CellKind {
+    Number,
+    Boolean,
+    Text,
+}
+
+impl CellKind {
+    fn of(cell: &str) -> Self {
+        match typing::infer_value(cell) {
+            Value::Number(_) => CellKind::Number,
+            Value::Bool(_) => CellKind::Boolean,
+            _ => CellKind::Text,
+        }
+    }
+}
+
+/// The value shared by all items, if there is at least one.
+fn uniform<T: PartialEq>(mut items: impl Iterator<Item = T>) -> Option<T> {
+    let first = items.next()?;
+    items.all(|item| item == first).then_some(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto_dialect() -> Dialect {
+        Dialect::from_params(None, Some("auto")).unwrap()
+    }
+
+    #[test]
+    fn test_sniff_delimiters() {
+        let dialect = auto_dialect();
+        let cases = [
+            ("name,age\nAlice,30\n", b','),
+            ("name;price\nTea;1,50\nCake;12,00\n", b';'),
+            ("name\tnote\nAlice\t\"a, b; c\"\n", b'\t'),
+            ("id|zone\n1|EWR\n2|JFK\n", b'|'),
+            ("name\nAlice\n", b','),
+        ];
+
+        for (input, delimiter) in cases {
+            let sniffed = sniff(input.as_bytes(), &dialect);
+            assert_eq!(sniffed.delimiter, delimiter, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_sniff_header() {
+        let dialect = Dialect::default();
+        let cases = [
+            ("id,score\n7,99.5\n8,12\n", true),
+            ("7,99.5\n8,12\n9,40\n", false),
+            ("code,city\nEWR,Newark\nJFK,Queens\n", true),
+            ("EWR,Newark\nJFK,Queens\nLGA,Queens\n", false),
+            ("name,city\nAlice,Paris\n", true),
+            ("name,city\n", true),
+        ];
+
+        for (input, has_header) in cases {
+            let sniffed = sniff(input.as_bytes(), &dialect);
+            assert_eq!(sniffed.has_header, has_header, "input: {:?}", input);
+        }
+    }
+}
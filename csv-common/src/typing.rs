@@ -0,0 +1,77 @@
+use serde_json::{Number, Value};
+
+/// Convert a CSV cell into a typed JSON value.
+///
+/// Empty cells become `null`, `true`/`false` become booleans and cells that are valid
+/// JSON number literals become numbers. Anything else, including identifiers with
+/// leading zeros such as zip codes, is kept as a string.
+pub fn infer_value(cell: &str) -> Value {
+    if cell.is_empty() {
+        return Value::Null;
+    }
+    if cell.eq_ignore_ascii_case("true") {
+        return Value::Bool(true);
+    }
+    if cell.eq_ignore_ascii_case("false") {
+        return Value::Bool(false);
+    }
+    infer_number(cell)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(cell.to_string()))
+}
+
+/// Convert a CSV cell into JSON, inferring its type when `infer_types` is set.
+pub fn cell_to_json(cell: &str, infer_types: bool) -> Value {
+    if infer_types {
+        infer_value(cell)
+    } else {
+        Value::String(cell.to_string())
+    }
+}
+
+/// Parse a JSON number literal, rejecting integers that would lose precision as floats.
+fn infer_number(cell: &str) -> Option<Number> {
+    let first = cell.chars().next()?;
+    if first != '-' && !first.is_ascii_digit() {
+        return None;
+    }
+    let number: Number = serde_json::from_str(cell).ok()?;
+    let is_integer_literal = !cell.contains(['.', 'e', 'E']);
+    if is_integer_literal && number.is_f64() {
+        return None;
+    }
+    Some(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_infer_scalars() {
+        assert_eq!(infer_value(""), Value::Null);
+        assert_eq!(infer_value("true"), json!(true));
+        assert_eq!(infer_value("FALSE"), json!(false));
+        assert_eq!(infer_value("42"), json!(42));
+        assert_eq!(infer_value("-7"), json!(-7));
+        assert_eq!(infer_value("0"), json!(0));
+        assert_eq!(infer_value("3.25"), json!(3.25));
+        assert_eq!(infer_value("-1.5e3"), json!(-1500.0));
+        assert_eq!(infer_value("Newark Airport"), json!("Newark Airport"));
+    }
+
+    #[test]
+    fn test_infer_keeps_identifiers_as_strings() {
+        assert_eq!(infer_value("02134"), json!("02134"));
+        assert_eq!(infer_value("00.5"), json!("00.5"));
+        assert_eq!(infer_value("+1"), json!("+1"));
+        assert_eq!(infer_value("1."), json!("1."));
+        assert_eq!(infer_value("NaN"), json!("NaN"));
+        assert_eq!(infer_value("inf"), json!("inf"));
+        assert_eq!(
+            infer_value("123456789012345678901234567890"),
+            json!("123456789012345678901234567890")
+        );
+    }
+}
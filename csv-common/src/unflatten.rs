@@ -0,0 +1,267 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+
+use fluvio_smartmodule::{eyre, Result};
+use serde_json::{Map, Value};
+
+/// Largest array index accepted in a bracketed header such as `tags[3]`.
+const MAX_INDEX: usize = 4096;
+
+/// One step of the path of an output field.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Split a header such as `address.city` or `tags[0]` into path segments.
+///
+/// Headers with empty parts or malformed brackets are kept as a single key.
+pub fn parse_path(name: &str, separator: &str) -> Vec<Segment> {
+    let mut path = Vec::new();
+    for part in name.split(separator) {
+        match parse_part(part) {
+            Some(segments) => path.extend(segments),
+            None => return vec![Segment::Key(name.to_string())],
+        }
+    }
+    path
+}
+
+/// Parse `key`, `key[0]` or `key[0][1]` into segments.
+fn parse_part(part: &str) -> Option<Vec<Segment>> {
+    let (key, mut rest) = match part.find('[') {
+        Some(start) => part.split_at(start),
+        None => (part, ""),
+    };
+    if key.is_empty() {
+        return None;
+    }
+
+    let mut segments = vec![Segment::Key(key.to_string())];
+    while let Some(inner) = rest.strip_prefix('[') {
+        let end = inner.find(']')?;
+        let index: usize = inner[..end].parse().ok()?;
+        if index > MAX_INDEX {
+            return None;
+        }
+        segments.push(Segment::Index(index));
+        rest = &inner[end + 1..];
+    }
+    rest.is_empty().then_some(segments)
+}
+
+/// Display a path the way it would appear in a header.
+pub fn display_path(path: &[Segment], separator: &str) -> String {
+    let mut out = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) if out.is_empty() => out.push_str(key),
+            Segment::Key(key) => {
+                out.push_str(separator);
+                out.push_str(key);
+            }
+            Segment::Index(index) => out.push_str(&format!("[{}]", index)),
+        }
+    }
+    out
+}
+
+/// Paths already claimed by earlier columns, used to detect conflicting headers.
+enum Node {
+    Leaf(String),
+    Branch(BTreeMap<Segment, Node>),
+}
+
+impl Node {
+    /// Name of any column stored at or below this node.
+    fn any_leaf(&self) -> &str {
+        match self {
+            Node::Leaf(name) => name,
+            Node::Branch(children) => children.values().next().map_or("", Node::any_leaf),
+        }
+    }
+}
+
+/// Fail when two columns map to the same field, or one is nested under the other.
+pub fn check_conflicts<'p>(
+    paths: impl IntoIterator<Item = &'p [Segment]>,
+    separator: &str,
+) -> Result<()> {
+    let mut root = Node::Branch(BTreeMap::new());
+
+    for path in paths {
+        let name = display_path(path, separator);
+        let conflict = |other: &str| eyre!("conflicting columns `{}` and `{}`", other, name);
+
+        let mut node = &mut root;
+        for (depth, segment) in path.iter().enumerate() {
+            let children = match node {
+                Node::Leaf(other) => return Err(conflict(other)),
+                Node::Branch(children) => children,
+            };
+
+            // Objects and arrays cannot share a path
+            if let Some((sibling, sibling_node)) = children.iter().next() {
+                if std::mem::discriminant(sibling) != std::mem::discriminant(segment) {
+                    return Err(conflict(sibling_node.any_leaf()));
+                }
+            }
+
+            let is_last = depth + 1 == path.len();
+            node = match children.entry(segment.clone()) {
+                Entry::Occupied(entry) if is_last => return Err(conflict(entry.get().any_leaf())),
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) if is_last => entry.insert(Node::Leaf(name.clone())),
+                Entry::Vacant(entry) => entry.insert(Node::Branch(BTreeMap::new())),
+            };
+        }
+    }
+
+    Ok(())
+}
+
+/// Insert a value at a path, creating nested objects and arrays along the way.
+///
+/// Array gaps are filled with `null`.
+pub fn insert_path(target: &mut Map<String, Value>, path: &[Segment], value: Value) {
+    let Some((Segment::Key(first), rest)) = path.split_first() else {
+        return;
+    };
+    if rest.is_empty() {
+        target.insert(first.clone(), value);
+        return;
+    }
+    let slot = target
+        .entry(first.clone())
+        .or_insert_with(|| empty_container(&rest[0]));
+    insert_into(slot, rest, value);
+}
+
+fn insert_into(slot: &mut Value, path: &[Segment], value: Value) {
+    let (segment, rest) = path.split_first().expect("path is not empty");
+    let next = match (slot, segment) {
+        (Value::Object(map), Segment::Key(key)) => {
+            if rest.is_empty() {
+                map.insert(key.clone(), value);
+                return;
+            }
+            map.entry(key.clone())
+                .or_insert_with(|| empty_container(&rest[0]))
+        }
+        (Value::Array(items), Segment::Index(index)) => {
+            if items.len() <= *index {
+                items.resize(index + 1, Value::Null);
+            }
+            if rest.is_empty() {
+                items[*index] = value;
+                return;
+            }
+            if items[*index].is_null() {
+                items[*index] = empty_container(&rest[0]);
+            }
+            &mut items[*index]
+        }
+        // Shapes are validated by `check_conflicts`
+        _ => return,
+    };
+    insert_into(next, rest, value);
+}
+
+/// Value at a path of a JSON row, if present.
+pub fn get_path<'v>(row: &'v Value, path: &[Segment]) -> Option<&'v Value> {
+    path.iter().try_fold(row, |value, segment| match segment {
+        Segment::Key(key) => value.get(key),
+        Segment::Index(index) => value.get(index),
+    })
+}
+
+fn empty_container(segment: &Segment) -> Value {
+    match segment {
+        Segment::Key(_) => Value::Object(Map::new()),
+        Segment::Index(_) => Value::Array(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(k: &str) -> Segment {
+        Segment::Key(k.to_string())
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(parse_path("address.city", "."), vec![key("address"), key("city")]);
+        assert_eq!(
+            parse_path("tags[0]", "."),
+            vec![key("tags"), Segment::Index(0)]
+        );
+        assert_eq!(
+            parse_path("a__b[1][2]", "__"),
+            vec![key("a"), key("b"), Segment::Index(1), Segment::Index(2)]
+        );
+        assert_eq!(parse_path("total.", "."), vec![key("total.")]);
+        assert_eq!(parse_path("[0]", "."), vec![key("[0]")]);
+        assert_eq!(parse_path("a[x]", "."), vec![key("a[x]")]);
+        assert_eq!(parse_path("a[99999]", "."), vec![key("a[99999]")]);
+    }
+
+    #[test]
+    fn test_insert_path() {
+        let mut row = Map::new();
+        insert_path(&mut row, &parse_path("address.city", "."), json!("Paris"));
+        insert_path(&mut row, &parse_path("address.zip", "."), json!("75001"));
+        insert_path(&mut row, &parse_path("tags[1]", "."), json!("b"));
+        insert_path(&mut row, &parse_path("items[0].sku", "."), json!("X1"));
+        insert_path(&mut row, &parse_path("id", "."), json!(1));
+
+        assert_eq!(
+            Value::Object(row),
+            json!({
+                "address": {"city": "Paris", "zip": "75001"},
+                "tags": [null, "b"],
+                "items": [{"sku": "X1"}],
+                "id": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_path() {
+        let row = json!({"address": {"city": "Paris"}, "tags": ["a", "b"], "id": 1});
+
+        assert_eq!(get_path(&row, &parse_path("address.city", ".")), Some(&json!("Paris")));
+        assert_eq!(get_path(&row, &parse_path("tags[1]", ".")), Some(&json!("b")));
+        assert_eq!(get_path(&row, &parse_path("id", ".")), Some(&json!(1)));
+        assert_eq!(get_path(&row, &parse_path("address.zip", ".")), None);
+    }
+
+    #[test]
+    fn test_check_conflicts() {
+        let check = |names: &[&str]| {
+            let paths: Vec<Vec<Segment>> = names.iter().map(|n| parse_path(n, ".")).collect();
+            check_conflicts(paths.iter().map(Vec::as_slice), ".")
+        };
+
+        assert!(check(&["a.b", "a.c", "t[0]", "t[1].x", "t[1].y"]).is_ok());
+        assert_eq!(
+            check(&["a", "a.b"]).unwrap_err().to_string(),
+            "conflicting columns `a` and `a.b`"
+        );
+        assert_eq!(
+            check(&["a.b", "a"]).unwrap_err().to_string(),
+            "conflicting columns `a.b` and `a`"
+        );
+        assert_eq!(
+            check(&["a.b", "a[0]"]).unwrap_err().to_string(),
+            "conflicting columns `a.b` and `a[0]`"
+        );
+        assert_eq!(
+            check(&["t[0]", "t[0]"]).unwrap_err().to_string(),
+            "conflicting columns `t[0]` and `t[0]`"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use fluvio_smartmodule::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+/// A slice of the rows of a CSV record, emitted as its own JSON record.
+#[derive(Debug, Serialize)]
+struct Chunk<R> {
+    /// Index of the chunk, starting at 0.
+    chunk: usize,
+    /// Number of chunks produced from the CSV record.
+    chunks: usize,
+    /// Index of the first row in the chunk, starting at 0.
+    first_row: usize,
+    /// Index of the last row in the chunk.
+    last_row: usize,
+    /// Number of rows produced from the CSV record.
+    total_rows: usize,
+    /// Rows of the chunk, in the output layout.
+    rows: R,
+}
+
+/// Split the rows into serialized chunks of at most `chunk_size` rows, each arranged
+/// by `arrange`.
+///
+/// A CSV record without rows produces no chunks.
+pub(crate) fn chunk_rows<'a, R: Serialize>(
+    rows: &'a [Value],
+    chunk_size: usize,
+    arrange: impl Fn(&'a [Value]) -> R,
+) -> Result<Vec<Vec<u8>>> {
+    let chunks = rows.len().div_ceil(chunk_size);
+
+    rows.chunks(chunk_size)
+        .enumerate()
+        .map(|(index, rows_in_chunk)| {
+            let first_row = index * chunk_size;
+            let chunk = Chunk {
+                chunk: index,
+                chunks,
+                first_row,
+                last_row: first_row + rows_in_chunk.len() - 1,
+                total_rows: rows.len(),
+                rows: arrange(rows_in_chunk),
+            };
+            Ok(serde_json::to_vec(&chunk)?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_chunk_rows() {
+        let rows: Vec<Value> = (0..5).map(|id| json!({"id": id})).collect();
+
+        let chunks: Vec<Value> = chunk_rows(&rows, 2, |rows_in_chunk| rows_in_chunk)
+            .unwrap()
+            .iter()
+            .map(|chunk| serde_json::from_slice(chunk).unwrap())
+            .collect();
+
+        assert_eq!(
+            chunks,
+            vec![
+                json!({"chunk": 0, "chunks": 3, "first_row": 0, "last_row": 1, "total_rows": 5, "rows": [{"id": 0}, {"id": 1}]}),
+                json!({"chunk": 1, "chunks": 3, "first_row": 2, "last_row": 3, "total_rows": 5, "rows": [{"id": 2}, {"id": 3}]}),
+                json!({"chunk": 2, "chunks": 3, "first_row": 4, "last_row": 4, "total_rows": 5, "rows": [{"id": 4}]}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_rows_empty() {
+        assert!(chunk_rows(&[], 10, |rows_in_chunk| rows_in_chunk).unwrap().is_empty());
+    }
+}
@@ -1,123 +1,156 @@
-use csv::{ReaderBuilder, Trim};
 use fluvio_smartmodule::{
-    dataplane::smartmodule::SmartModuleExtraParams, smartmodule, RecordData, Result,
+    dataplane::smartmodule::SmartModuleExtraParams, eyre, smartmodule, RecordData, Result,
     SmartModuleRecord,
 };
-use heck::{ToLowerCamelCase, ToSnakeCase};
-use serde_json::{json, Value};
+use serde_json::Value;
 use std::sync::OnceLock;
 
+use csv_common::params::CsvParams;
+use csv_common::row::RowLayout;
+use csv_common::sniff;
+
+mod chunk;
+mod output;
+
+use output::OutputLayout;
+
 static PARAMS: OnceLock<Params> = OnceLock::new();
-const DELIMITER_PARAM_NAME: &str = "delimiter";
-const HEADER_CASE_PARAM_NAME: &str = "header_case";
-const DEFAULT_DELIMITER: u8 = b',';
+const CHUNK_SIZE_PARAM_NAME: &str = "chunk_size";
+const OUTPUT_LAYOUT_PARAM_NAME: &str = "output_layout";
 
-#[smartmodule(map)]
-pub fn map(record: &SmartModuleRecord) -> Result<(Option<RecordData>, RecordData)> {
+#[smartmodule(array_map)]
+pub fn array_map(record: &SmartModuleRecord) -> Result<Vec<(Option<RecordData>, RecordData)>> {
     let params = PARAMS.get().expect("params is not initialized");
 
     let key = record.key.clone();
-    let value = process_csv_record(record, &params)?;
-    
-    Ok((key, RecordData::from(value)))
+    let values = match params.chunk_size {
+        Some(chunk_size) => {
+            let (fields, rows) = read_csv_rows(record, params)?;
+            let columns = params.output_layout.columns(fields, &rows);
+            chunk::chunk_rows(&rows, chunk_size, |rows_in_chunk| {
+                params.output_layout.arrange(&columns, rows_in_chunk)
+            })?
+        }
+        None => vec![process_csv_record(record, params)?],
+    };
+
+    Ok(values
+        .into_iter()
+        .map(|value| (key.clone(), RecordData::from(value)))
+        .collect())
 }
 
 fn process_csv_record(record: &SmartModuleRecord, params: &Params) -> Result<Vec<u8>> {
-    // Initialize CSV reader with the specified delimiter and other settings
-    let mut csv_reader = ReaderBuilder::new()
-        .delimiter(params.delimiter)
-        .has_headers(true)
-        .trim(Trim::All)
-        .from_reader(record.value.as_ref());
+    let (fields, rows) = read_csv_rows(record, params)?;
+    let columns = params.output_layout.columns(fields, &rows);
 
-    let mut rows: Vec<Value> = Vec::new();
+    // Serialize the rows into a JSON byte vector
+    let serialized_output = serde_json::to_vec(&params.output_layout.arrange(&columns, &rows))?;
 
-    // Collect headers and apply the header case transformation
-    let headers: Vec<String> = csv_reader
-        .headers()?
-        .iter()
-        .map(|h| match params.header_case {
-            HeaderCase::Camel => h.to_lower_camel_case(),
-            HeaderCase::Snake => h.to_snake_case(),
-            HeaderCase::None => h.to_string(),
-        })
-        .collect();
+    Ok(serialized_output)
+}
 
-    // Collect all records as Vec<Vec<String>>
-    let records: Vec<Vec<String>> = csv_reader.records()
-        .map(|record| record.unwrap().iter().map(|s| s.to_string()).collect())
-        .collect();
+/// Convert all CSV rows of a record into JSON objects, along with the top-level output
+/// field names in header order.
+fn read_csv_rows(
+    record: &SmartModuleRecord,
+    params: &Params,
+) -> Result<(Vec<String>, Vec<Value>)> {
+    let decoded = params.csv.encoding.decode(record.value.as_ref())?;
+
+    // Initialize CSV reader with the configured dialect
+    let input = params.csv.dialect.prepare(&decoded)?;
+
+    // Sniff the delimiter and the header line of this record when asked to
+    let sniffed = params
+        .csv
+        .sniffs()
+        .then(|| sniff::sniff(&input, &params.csv.dialect));
+
+    let mut builder = params.csv.dialect.reader_builder();
+    if let Some(sniffed) = &sniffed {
+        builder.delimiter(sniffed.delimiter);
+    }
+    let mut csv_reader = builder
+        .has_headers(params.csv.headers.in_first_row(sniffed.as_ref()))
+        .flexible(true)
+        .from_reader(input.as_ref());
+
+    let mut rows: Vec<Value> = Vec::new();
+
+    // Resolve output columns from the headers, the header case and the schema
+    let headers = params
+        .csv
+        .headers
+        .resolve(
+            &params.csv.dialect.restore(csv_reader.headers()?.clone()),
+            sniffed.as_ref(),
+        );
+    let layout = RowLayout::new(&headers, &params.csv)?;
 
     // Iterate over all records and convert each to a JSON object
-    for record in records.iter() {
-        let json_object: Value = headers
-            .iter()
-            .zip(record.iter())
-            .map(|(key, value)| (key.clone(), json!(value)))
-            .collect();
-        rows.push(json_object);
+    for result in csv_reader.records() {
+        let result = result.map(|row| params.csv.dialect.restore(row));
+        match layout.convert(result, &params.csv) {
+            Ok(Some(json_object)) => rows.push(json_object),
+            Ok(None) => {}
+            Err(err) => rows.extend(
+                params
+                    .csv
+                    .on_error
+                    .handle(err, &input, &params.csv.dialect)?,
+            ),
+        }
     }
 
-    // Serialize the rows into a JSON byte vector
-    let serialized_output = serde_json::to_vec(&rows)?;
-
-    Ok(serialized_output)
+    Ok((layout.field_names(), rows))
 }
 
 #[smartmodule(init)]
 fn init(params: SmartModuleExtraParams) -> Result<()> {
-    let delimiter_param = params
-        .get(DELIMITER_PARAM_NAME)
-        .map_or(DEFAULT_DELIMITER, |v| {
-            v.chars().next().expect("delimiter is empty") as u8
-        });
-
-    let case_param = params
-        .get(HEADER_CASE_PARAM_NAME)
-        .map_or(HeaderCase::None, |v| {
-            v.to_string().try_into().unwrap_or_else(|e| {
-                panic!("failed to parse header case: {}", e);
-            })
-        });
+    let chunk_size_param = match params.get(CHUNK_SIZE_PARAM_NAME) {
+        Some(v) => match v.trim().parse::<usize>() {
+            Ok(size) if size > 0 => Some(size),
+            _ => {
+                return Err(eyre!(
+                    "invalid `chunk_size` param: expected a positive integer, got `{}`",
+                    v
+                ))
+            }
+        },
+        None => None,
+    };
+
+    let output_layout_param = match params.get(OUTPUT_LAYOUT_PARAM_NAME) {
+        Some(v) => OutputLayout::try_from(v.to_string())
+            .map_err(|e| eyre!("failed to parse output_layout: {}", e))?,
+        None => OutputLayout::Rows,
+    };
 
     PARAMS
-        .set(Params::new(delimiter_param, case_param))
+        .set(Params {
+            chunk_size: chunk_size_param,
+            output_layout: output_layout_param,
+            ..Params::from(CsvParams::from_params(&params)?)
+        })
         .expect("params is already initialized");
 
     Ok(())
 }
 
-
-#[derive(Debug)]
+/// Params of the SmartModule; the CSV params are shared with csv-json-records.
+#[derive(Debug, Default)]
 struct Params {
-    delimiter: u8,
-    header_case: HeaderCase,
+    csv: CsvParams,
+    chunk_size: Option<usize>,
+    output_layout: OutputLayout,
 }
 
-#[derive(Debug)]
-enum HeaderCase {
-    Camel,
-    Snake,
-    None,
-}
-
-impl TryFrom<String> for HeaderCase {
-    type Error = &'static str;
-    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
-        match s.to_lowercase().as_str() {
-            "camel" => Ok(HeaderCase::Camel),
-            "snake" => Ok(HeaderCase::Snake),
-            "" | "none" => Ok(HeaderCase::None),
-            _ => Err("Invalid header case"),
-        }
-    }
-}
-
-impl Params {
-    fn new(delimiter: u8, header_case: HeaderCase) -> Self {
+impl From<CsvParams> for Params {
+    fn from(csv: CsvParams) -> Self {
         Self {
-            delimiter,
-            header_case,
+            csv,
+            ..Self::default()
         }
     }
 }
@@ -125,6 +158,13 @@ impl Params {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use csv_common::case::HeaderCase;
+    use csv_common::dialect::Dialect;
+    use csv_common::encoding::InputEncoding;
+    use csv_common::error::OnError;
+    use csv_common::filter::{Projection, RowFilter};
+    use csv_common::params::{parse_header_map, Headers, RaggedRows, DEFAULT_DELIMITER};
+    use csv_common::schema::Schema;
     use fluvio_smartmodule::{Record, RecordData, SmartModuleRecord};
     use fluvio_smartmodule::dataplane::record::RecordHeader;
     use serde_json::json;
@@ -144,7 +184,7 @@ mod tests {
             headers: 0,
         };
         let record = SmartModuleRecord::new(data_record, 0, 0);
-        let params = Params::new(DEFAULT_DELIMITER, HeaderCase::None);
+        let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, HeaderCase::None));
 
         // Test Conversion
         let result =process_csv_record(&record, &params).unwrap();
@@ -172,7 +212,7 @@ mod tests {
             headers: 0,
         };
         let record = SmartModuleRecord::new(data_record, 0, 0);
-        let params = Params::new(DEFAULT_DELIMITER, HeaderCase::Camel);
+        let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Camel));
 
         // Compute CSV
         let result =process_csv_record(&record, &params).unwrap();
@@ -197,7 +237,7 @@ mod tests {
             headers: 0,
         };
         let record = SmartModuleRecord::new(data_record, 0, 0);
-        let params = Params::new(DEFAULT_DELIMITER, HeaderCase::Snake);
+        let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake));
 
         // Test Conversion
         let result = process_csv_record(&record, &params).unwrap();
@@ -222,7 +262,7 @@ mod tests {
             headers: 0,
         };
         let record = SmartModuleRecord::new(data_record, 0, 0);
-        let params = Params::new(DEFAULT_DELIMITER, HeaderCase::None);
+        let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, HeaderCase::None));
 
         // Test Conversion
         let result = process_csv_record(&record, &params).unwrap();
@@ -233,4 +273,580 @@ mod tests {
 
         assert_eq!(result, expected_output);
     }
+
+    #[test]
+    fn test_infer_types_conversion() {
+        let csv_data = "LocationID,Zip,Rate,Active,Note\n1,02134,4.5,true,\n2,07030,-0.25,FALSE,late\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            infer_types: true,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"LocationID": 1, "Zip": "02134", "Rate": 4.5, "Active": true, "Note": null}),
+            json!({"LocationID": 2, "Zip": "07030", "Rate": -0.25, "Active": false, "Note": "late"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+        fn test_schema_conversion() {
+        let csv_data = "LocationID,Borough,Zone\n1,EWR,Newark Airport\n2,,Jamaica Bay\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let schema = r#"[
+            {"column": "LocationID", "type": "integer", "rename": "location_id", "required": true},
+            {"column": "Borough", "default": "Unknown"},
+            {"column": "service_zone", "default": "N/A"}
+        ]"#;
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(schema).unwrap(),
+            ..CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake)
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"location_id": 1, "borough": "EWR", "zone": "Newark Airport", "service_zone": "N/A"}),
+            json!({"location_id": 2, "borough": "Unknown", "zone": "Jamaica Bay", "service_zone": "N/A"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_schema_rejects_missing_required_column() {
+        let csv_data = "name\nAlice\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(r#"[{"column": "id", "required": true}]"#).unwrap(),
+            ..CsvParams::default()
+        });
+
+        let err = process_csv_record(&record, &params).unwrap_err();
+        assert_eq!(err.to_string(), "required column `id` is missing from the header");
+    }
+
+    #[test]
+    fn test_headerless_auto_names() {
+        let csv_data = "1,EWR,Newark Airport\n2,Queens,Jamaica Bay\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            headers: Headers::Auto,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"col_0": "1", "col_1": "EWR", "col_2": "Newark Airport"}),
+            json!({"col_0": "2", "col_1": "Queens", "col_2": "Jamaica Bay"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_headerless_explicit_names_with_case() {
+        let csv_data = "1,EWR,Newark Airport\n2,Queens,Jamaica Bay\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            headers: Headers::parse("LocationID,Borough,Zone").unwrap(),
+            ..CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake)
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"location_id": "1", "borough": "EWR", "zone": "Newark Airport"}),
+            json!({"location_id": "2", "borough": "Queens", "zone": "Jamaica Bay"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_on_error_skip() {
+        let csv_data = "name,age\nAlice,30\nBob,25,extra\nCharlie,35\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            on_error: OnError::Skip,
+            ragged_rows: RaggedRows::Strict,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"name": "Alice", "age": "30"}),
+            json!({"name": "Charlie", "age": "35"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_on_error_emit() {
+        let csv_data = "name,age\nAlice,30\nBob\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            on_error: OnError::Emit,
+            ragged_rows: RaggedRows::Strict,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"name": "Alice", "age": "30"}),
+            json!({
+                "error": "found 1 fields, expected 2",
+                "line": 3,
+                "byte": 18,
+                "raw": "Bob",
+            }),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_on_error_fail() {
+        let csv_data = "id,name\n1,Alice\nx,Bob\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(r#"[{"column": "id", "type": "integer"}]"#).unwrap(),
+            ..CsvParams::default()
+        });
+
+        let err = process_csv_record(&record, &params).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, byte 16: column `id`: cannot convert `x` to integer"
+        );
+    }
+
+    #[test]
+    fn test_ragged_rows_fill() {
+        let csv_data = "id,name,zone\n1,Alice\n2,Bob,Queens,late,7\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            missing_value: json!("n/a"),
+            extra_field: "overflow".to_string(),
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"id": "1", "name": "Alice", "zone": "n/a"}),
+            json!({"id": "2", "name": "Bob", "zone": "Queens", "overflow": ["late", "7"]}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_extra_field_collides_with_column() {
+        let csv_data = "id,_extra\n1,note\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+
+        let err = process_csv_record(&record, &Params::default()).unwrap_err();
+        assert_eq!(err.to_string(), "extra field `_extra` collides with a column");
+
+        // Strict rows have no extra cells
+        let params = Params::from(CsvParams {
+            ragged_rows: RaggedRows::Strict,
+            ..CsvParams::default()
+        });
+        assert!(process_csv_record(&record, &params).is_ok());
+    }
+
+    #[test]
+    fn test_ragged_rows_schema_default() {
+        let csv_data = "id,name,zone\n1,Alice\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(r#"[{"column": "zone", "default": "Unknown"}]"#).unwrap(),
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![json!({"id": "1", "name": "Alice", "zone": "Unknown"})];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_unflatten_headers() {
+        let csv_data = "ID,Address.City,Address.Zip Code,Tags[0],Tags[1]\n1,Paris,75001,new,vip\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            unflatten: true,
+            ..CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake)
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![json!({
+            "id": "1",
+            "address": {"city": "Paris", "zip_code": "75001"},
+            "tags": ["new", "vip"],
+        })];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_unflatten_conflicting_headers() {
+        let csv_data = "a,a/b\n1,2\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            unflatten: true,
+            path_separator: "/".to_string(),
+            ..CsvParams::default()
+        });
+
+        let err = process_csv_record(&record, &params).unwrap_err();
+        assert_eq!(err.to_string(), "conflicting columns `a` and `a/b`");
+    }
+
+    #[test]
+    fn test_windows_1252_encoding() {
+        let csv_data: &[u8] = b"city,price\nZ\xFCrich,\x8012\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            encoding: InputEncoding::parse("windows-1252").unwrap(),
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![json!({"city": "Zürich", "price": "€12"})];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_utf8_bom_headerless() {
+        let csv_data = "\u{feff}1,EWR\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            headers: Headers::Auto,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![json!({"col_0": "1", "col_1": "EWR"})];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_chunked_output() {
+        let csv_data = "id,name\n1,a\n2,b\n3,c\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params {
+            chunk_size: Some(2),
+            ..Params::default()
+        };
+
+        // Test Conversion
+        let (_, rows) = read_csv_rows(&record, &params).unwrap();
+        let result: Vec<Value> = chunk::chunk_rows(&rows, 2, |rows_in_chunk| rows_in_chunk)
+            .unwrap()
+            .iter()
+            .map(|chunk| serde_json::from_slice(chunk).unwrap())
+            .collect();
+
+        // Prepare Result
+        let expected_output = vec![
+            json!({
+                "chunk": 0, "chunks": 2, "first_row": 0, "last_row": 1, "total_rows": 3,
+                "rows": [{"id": "1", "name": "a"}, {"id": "2", "name": "b"}],
+            }),
+            json!({
+                "chunk": 1, "chunks": 2, "first_row": 2, "last_row": 2, "total_rows": 3,
+                "rows": [{"id": "3", "name": "c"}],
+            }),
+        ];
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_projection_and_filter() {
+        let csv_data = "id,name,status,amount\n1,a,active,10\n2,b,closed,20\n3,c,active,30\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            projection: Projection::Include(vec!["id".to_string(), "amount".to_string()]),
+            filter: RowFilter::parse(
+                r#"[{"column": "status", "op": "eq", "value": "active"},
+                    {"column": "amount", "op": "gt", "value": 15}]"#,
+            )
+            .unwrap(),
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let result = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![json!({"id": "3", "amount": "30"})];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(result, expected_output);
+    }
+
+    #[test]
+    fn test_include_unknown_column() {
+        let csv_data = "id,name\n1,a\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            projection: Projection::Include(vec!["email".to_string()]),
+            ..CsvParams::default()
+        });
+
+        let err = process_csv_record(&record, &params).unwrap_err();
+        assert_eq!(err.to_string(), "included column `email` is missing from the header");
+    }
+
+    #[test]
+    fn test_extended_header_cases() {
+        let csv_data = "first name,zip_code\nAlice,75001\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+
+        for (case, expected_json) in [
+            (HeaderCase::Pascal, json!({"FirstName": "Alice", "ZipCode": "75001"})),
+            (HeaderCase::Kebab, json!({"first-name": "Alice", "zip-code": "75001"})),
+            (HeaderCase::Constant, json!({"FIRST_NAME": "Alice", "ZIP_CODE": "75001"})),
+            (HeaderCase::Cobol, json!({"FIRST-NAME": "Alice", "ZIP-CODE": "75001"})),
+        ] {
+            let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, case));
+
+            // Test Conversion
+            let computed_output = process_csv_record(&record, &params).unwrap();
+
+            // Prepare Result
+            let expected_output = serde_json::to_vec(&vec![expected_json]).unwrap();
+
+            assert_eq!(computed_output, expected_output);
+        }
+    }
+
+    #[test]
+    fn test_header_map_and_duplicate_headers() {
+        let csv_data = "Zone,Zone Name,zone-name,---,Borough\nEWR,Newark,N,x,Queens\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            header_map: parse_header_map(r#"{"Borough": "BoroughName", "Zone": "zone_name"}"#)
+                .unwrap(),
+            ..CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake)
+        });
+
+        // Test Conversion
+        let computed_output = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![json!({
+            "zone_name": "EWR",
+            "zone_name_1": "Newark",
+            "zone_name_2": "N",
+            "_1": "x",
+            "BoroughName": "Queens",
+        })];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_schema_datetime() {
+        let csv_data = "id,created\n1,2024-03-04T13:00:00+01:00\n2,04.03.2024\n3,n/a\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let schema = r#"[{
+            "column": "created",
+            "datetime": {"formats": ["rfc3339", "%d.%m.%Y"], "timezone": "+02:00"}
+        }]"#;
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(schema).unwrap(),
+            on_error: OnError::Emit,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed_output = process_csv_record(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"id": "1", "created": "2024-03-04T12:00:00Z"}),
+            json!({"id": "2", "created": "2024-03-03T22:00:00Z"}),
+            json!({
+                "error": "column `created`: `n/a` does not match any datetime format",
+                "line": 4,
+                "byte": 52,
+                "raw": "3,n/a",
+            }),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_sniffed_dialect() {
+        let params = Params::from(CsvParams {
+            dialect: Dialect::from_params(None, Some("auto")).unwrap(),
+            headers: Headers::Detect,
+            ..CsvParams::default()
+        });
+        let cases = [
+            (
+                "Zone|Borough\nEWR|Newark\n",
+                vec![json!({"Zone": "EWR", "Borough": "Newark"})],
+            ),
+            (
+                "EWR;Newark\nJFK;Queens\n",
+                vec![
+                    json!({"col_0": "EWR", "col_1": "Newark"}),
+                    json!({"col_0": "JFK", "col_1": "Queens"}),
+                ],
+            ),
+        ];
+
+        for (csv_data, expected_json) in cases {
+            let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+
+            // Test Conversion
+            let computed_output = process_csv_record(&record, &params).unwrap();
+
+            // Prepare Result
+            let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+            assert_eq!(computed_output, expected_output);
+        }
+    }
+
+    #[test]
+    fn test_output_layouts() {
+        let csv_data = "id,zone,rate\n1,EWR,2.5\n2,JFK,\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let cases = [
+            (
+                OutputLayout::Rows,
+                json!([
+                    {"id": 1, "zone": "EWR", "rate": 2.5},
+                    {"id": 2, "zone": "JFK", "rate": null},
+                ]),
+            ),
+            (
+                OutputLayout::Columnar,
+                json!({"id": [1, 2], "zone": ["EWR", "JFK"], "rate": [2.5, null]}),
+            ),
+            (
+                OutputLayout::Table,
+                json!({
+                    "columns": ["id", "zone", "rate"],
+                    "rows": [[1, "EWR", 2.5], [2, "JFK", null]],
+                }),
+            ),
+        ];
+
+        for (output_layout, expected_json) in cases {
+            let params = Params {
+                output_layout,
+                ..Params::from(CsvParams {
+                    infer_types: true,
+                    ..CsvParams::default()
+                })
+            };
+
+            // Test Conversion
+            let computed_output = process_csv_record(&record, &params).unwrap();
+
+            // Prepare Result
+            let result: Value = serde_json::from_slice(&computed_output).unwrap();
+
+            assert_eq!(result, expected_json);
+        }
+    }
+
+    #[test]
+    fn test_table_layout_header_order_and_chunks() {
+        let csv_data = "zone,id\nEWR,1\nJFK,2\nLGA,3,late\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params {
+            output_layout: OutputLayout::Table,
+            ..Params::default()
+        };
+
+        // Test Conversion
+        let (fields, rows) = read_csv_rows(&record, &params).unwrap();
+        let columns = params.output_layout.columns(fields, &rows);
+        let result: Vec<Value> = chunk::chunk_rows(&rows, 2, |rows_in_chunk| {
+            params.output_layout.arrange(&columns, rows_in_chunk)
+        })
+        .unwrap()
+        .iter()
+        .map(|chunk| serde_json::from_slice(chunk).unwrap())
+        .collect();
+
+        // Prepare Result
+        let expected_output = vec![
+            json!({
+                "chunk": 0, "chunks": 2, "first_row": 0, "last_row": 1, "total_rows": 3,
+                "rows": {
+                    "columns": ["zone", "id", "_extra"],
+                    "rows": [["EWR", "1", null], ["JFK", "2", null]],
+                },
+            }),
+            json!({
+                "chunk": 1, "chunks": 2, "first_row": 2, "last_row": 2, "total_rows": 3,
+                "rows": {
+                    "columns": ["zone", "id", "_extra"],
+                    "rows": [["LGA", "3", ["late"]]],
+                },
+            }),
+        ];
+
+        assert_eq!(result, expected_output);
+    }
 }
@@ -0,0 +1,173 @@
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
+use serde_json::Value;
+
+static NULL: Value = Value::Null;
+
+/// Shape of the JSON produced from the rows of a CSV record.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum OutputLayout {
+    /// An array of row objects.
+    #[default]
+    Rows,
+    /// An object of column names to the array of their values.
+    Columnar,
+    /// An object with the list of column names and an array of value arrays.
+    Table,
+}
+
+impl TryFrom<String> for OutputLayout {
+    type Error = &'static str;
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "" | "rows" => Ok(OutputLayout::Rows),
+            "columnar" => Ok(OutputLayout::Columnar),
+            "table" => Ok(OutputLayout::Table),
+            _ => Err("Invalid output layout"),
+        }
+    }
+}
+
+impl OutputLayout {
+    /// Column names of the layout: the header fields in order, followed by any other
+    /// field found in the rows, such as the extra field or the keys of error objects.
+    ///
+    /// The `rows` layout has no columns.
+    pub(crate) fn columns(self, fields: Vec<String>, rows: &[Value]) -> Vec<String> {
+        if self == OutputLayout::Rows {
+            return Vec::new();
+        }
+
+        let mut columns = fields;
+        for row in rows {
+            if let Value::Object(json_object) = row {
+                for key in json_object.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+        }
+        columns
+    }
+
+    /// Arrange the rows into the layout, for serialization.
+    pub(crate) fn arrange<'a>(self, columns: &'a [String], rows: &'a [Value]) -> Arranged<'a> {
+        Arranged {
+            layout: self,
+            columns,
+            rows,
+        }
+    }
+}
+
+/// Rows arranged in an output layout, serialized without copying the rows.
+#[derive(Debug)]
+pub(crate) struct Arranged<'a> {
+    layout: OutputLayout,
+    columns: &'a [String],
+    rows: &'a [Value],
+}
+
+impl Serialize for Arranged<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.layout {
+            OutputLayout::Rows => self.rows.serialize(serializer),
+            OutputLayout::Columnar => {
+                let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+                for column in self.columns {
+                    map.serialize_entry(
+                        column,
+                        &Cells(self.rows.iter().map(|row| cell(row, column))),
+                    )?;
+                }
+                map.end()
+            }
+            OutputLayout::Table => {
+                let mut table = serializer.serialize_struct("Table", 2)?;
+                table.serialize_field("columns", self.columns)?;
+                table.serialize_field(
+                    "rows",
+                    &Cells(self.rows.iter().map(|row| {
+                        Cells(self.columns.iter().map(move |column| cell(row, column)))
+                    })),
+                )?;
+                table.end()
+            }
+        }
+    }
+}
+
+/// A sequence of cells, serialized as a JSON array.
+struct Cells<I>(I);
+
+impl<I, T> Serialize for Cells<I>
+where
+    I: Iterator<Item = T> + Clone,
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.clone())
+    }
+}
+
+/// Value of a column in a row; `null` when the row lacks the column.
+fn cell<'a>(row: &'a Value, column: &str) -> &'a Value {
+    row.get(column).unwrap_or(&NULL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn arrange(layout: OutputLayout, rows: &[Value]) -> Value {
+        let fields = vec!["id".to_string(), "name".to_string()];
+        let columns = layout.columns(fields, rows);
+        serde_json::to_value(layout.arrange(&columns, rows)).unwrap()
+    }
+
+    #[test]
+    fn test_layouts() {
+        let rows = vec![
+            json!({"id": 1, "name": "a"}),
+            json!({"id": 2, "name": null, "_extra": [true]}),
+        ];
+
+        assert_eq!(arrange(OutputLayout::Rows, &rows), json!(rows));
+        assert_eq!(
+            arrange(OutputLayout::Columnar, &rows),
+            json!({"id": [1, 2], "name": ["a", null], "_extra": [null, [true]]})
+        );
+        assert_eq!(
+            arrange(OutputLayout::Table, &rows),
+            json!({
+                "columns": ["id", "name", "_extra"],
+                "rows": [[1, "a", null], [2, null, [true]]],
+            })
+        );
+    }
+
+    #[test]
+    fn test_layouts_without_rows() {
+        assert_eq!(arrange(OutputLayout::Rows, &[]), json!([]));
+        assert_eq!(
+            arrange(OutputLayout::Columnar, &[]),
+            json!({"id": [], "name": []})
+        );
+        assert_eq!(
+            arrange(OutputLayout::Table, &[]),
+            json!({"columns": ["id", "name"], "rows": []})
+        );
+    }
+
+    #[test]
+    fn test_columnar_keeps_header_order() {
+        let rows = vec![json!({"zone": "a", "id": 1})];
+        let columns =
+            OutputLayout::Columnar.columns(vec!["zone".to_string(), "id".to_string()], &rows);
+        let output =
+            serde_json::to_string(&OutputLayout::Columnar.arrange(&columns, &rows)).unwrap();
+
+        assert_eq!(output, r#"{"zone":["a"],"id":[1]}"#);
+    }
+}
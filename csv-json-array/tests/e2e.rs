@@ -31,6 +31,46 @@ fn csv_with_transit_data() {
     );
 }
 
+#[test]
+fn csv_with_inferred_types() {
+    csv_cmd(
+        "test-typed",
+        "test-data/typed/output.json",
+    );
+}
+
+#[test]
+fn csv_without_header_line() {
+    csv_cmd(
+        "test-headerless",
+        "test-data/headerless/output.json",
+    );
+}
+
+#[test]
+fn csv_with_tab_dialect() {
+    csv_cmd(
+        "test-tab",
+        "test-data/tab/output.json",
+    );
+}
+
+#[test]
+fn csv_with_sniffed_dialect() {
+    csv_cmd(
+        "test-semicolon-auto",
+        "test-data/semicolon-snake/output.json",
+    );
+}
+
+#[test]
+fn csv_with_columnar_layout() {
+    csv_cmd(
+        "test-typed-columnar",
+        "test-data/typed/output-columnar.json",
+    );
+}
+
 fn csv_cmd(arg: &str, output_file_path: &str) {
     // Read the expected output from a file
     let output_file = File::open(output_file_path).expect("file not found");
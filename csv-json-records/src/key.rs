@@ -0,0 +1,109 @@
+use csv_common::row::RowLayout;
+use csv_common::unflatten;
+use fluvio_smartmodule::{eyre, RecordData, Result};
+use serde_json::Value;
+
+/// How the Fluvio record key of each output row is built.
+#[derive(Debug, Default)]
+pub(crate) enum RecordKey {
+    /// The whole JSON row.
+    #[default]
+    Row,
+    /// The values of the named output columns, joined with a separator.
+    Columns {
+        columns: Vec<String>,
+        separator: String,
+    },
+    /// No key.
+    None,
+}
+
+impl RecordKey {
+    /// Build the record key for a JSON row and its serialized form.
+    ///
+    /// Key columns are looked up by output field name in the layout the row was built
+    /// with, so nested fields are found by their path.
+    pub(crate) fn build(
+        &self,
+        row: &Value,
+        layout: &RowLayout,
+        serialized_row: &[u8],
+    ) -> Result<Option<RecordData>> {
+        match self {
+            RecordKey::Row => Ok(Some(RecordData::from(serialized_row.to_vec()))),
+            RecordKey::Columns { columns, separator } => {
+                let parts = columns
+                    .iter()
+                    .map(|column| {
+                        let value = layout
+                            .field_path(column)
+                            .and_then(|path| unflatten::get_path(row, path))
+                            .ok_or_else(|| eyre!("key column `{}` is not in the row", column))?;
+                        Ok(match value {
+                            Value::String(s) => s.clone(),
+                            Value::Null => String::new(),
+                            value => value.to_string(),
+                        })
+                    })
+                    .collect::<Result<Vec<String>>>()?;
+                Ok(Some(RecordData::from(parts.join(separator))))
+            }
+            RecordKey::None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csv::StringRecord;
+    use csv_common::params::CsvParams;
+    use serde_json::json;
+
+    #[test]
+    fn test_build_from_columns() {
+        let key = RecordKey::Columns {
+            columns: vec!["borough".to_string(), "id".to_string(), "note".to_string()],
+            separator: ":".to_string(),
+        };
+        let params = CsvParams::default();
+        let headers = StringRecord::from(vec!["borough", "id", "note", "zone"]);
+        let layout = RowLayout::new(&headers, &params).unwrap();
+        let row = json!({"borough": "Queens", "id": 2, "note": null, "zone": "Jamaica Bay"});
+
+        let record_key = key.build(&row, &layout, &[]).unwrap().unwrap();
+        assert_eq!(record_key.as_ref(), b"Queens:2:");
+    }
+
+    #[test]
+    fn test_build_rejects_unknown_column() {
+        let key = RecordKey::Columns {
+            columns: vec!["region".to_string()],
+            separator: ":".to_string(),
+        };
+
+        let params = CsvParams::default();
+        let layout = RowLayout::new(&StringRecord::from(vec!["id"]), &params).unwrap();
+
+        let err = key.build(&json!({"id": 1}), &layout, &[]).unwrap_err();
+        assert_eq!(err.to_string(), "key column `region` is not in the row");
+    }
+
+    #[test]
+    fn test_build_from_nested_columns() {
+        let key = RecordKey::Columns {
+            columns: vec!["address.city".to_string(), "id".to_string()],
+            separator: ":".to_string(),
+        };
+        let params = CsvParams {
+            unflatten: true,
+            ..CsvParams::default()
+        };
+        let headers = StringRecord::from(vec!["id", "address.city"]);
+        let layout = RowLayout::new(&headers, &params).unwrap();
+        let row = json!({"id": "7", "address": {"city": "Paris"}});
+
+        let record_key = key.build(&row, &layout, &[]).unwrap().unwrap();
+        assert_eq!(record_key.as_ref(), b"Paris:7");
+    }
+}
@@ -1,133 +1,296 @@
-use csv::{ReaderBuilder, Trim};
+use csv::ReaderBuilder;
 use fluvio_smartmodule::{
-    dataplane::smartmodule::SmartModuleExtraParams, smartmodule, RecordData, Result,
+    dataplane::smartmodule::SmartModuleExtraParams, eyre, smartmodule, RecordData, Result,
     SmartModuleRecord,
 };
-use heck::{ToLowerCamelCase, ToSnakeCase};
-use serde_json::{json, Value};
+use regex::Regex;
+use serde_json::Value;
 use std::sync::OnceLock;
 
+use csv_common::error::RowError;
+use csv_common::params::{parse_bool, parse_list, CsvParams};
+use csv_common::row::RowLayout;
+use csv_common::sniff::{self, Sniffed};
+
+mod key;
+mod metadata;
+mod stream;
+
+use key::RecordKey;
+use metadata::Metadata;
+use stream::STREAM_STATE;
+
 static PARAMS: OnceLock<Params> = OnceLock::new();
-const DELIMITER_PARAM_NAME: &str = "delimiter";
-const HEADER_CASE_PARAM_NAME: &str = "header_case";
-const DEFAULT_DELIMITER: u8 = b',';
+const RECORD_KEY_PARAM_NAME: &str = "record_key";
+const KEY_COLUMNS_PARAM_NAME: &str = "key_columns";
+const KEY_SEPARATOR_PARAM_NAME: &str = "key_separator";
+const MODE_PARAM_NAME: &str = "mode";
+const HEADER_PATTERN_PARAM_NAME: &str = "header_pattern";
+const HEADER_REPEATS_PARAM_NAME: &str = "header_repeats";
+const ROW_NUMBER_FIELD_PARAM_NAME: &str = "row_number_field";
+const OFFSET_FIELD_PARAM_NAME: &str = "offset_field";
+const TIMESTAMP_FIELD_PARAM_NAME: &str = "timestamp_field";
+const SOURCE_PARAM_NAME: &str = "source";
+const SOURCE_FIELD_PARAM_NAME: &str = "source_field";
+const DIALECT_FIELD_PARAM_NAME: &str = "dialect_field";
+const DEFAULT_KEY_SEPARATOR: &str = ":";
+const DEFAULT_SOURCE_FIELD: &str = "_source";
 
 #[smartmodule(array_map)]
 pub fn array_map(record: &SmartModuleRecord) -> Result<Vec<(Option<RecordData>, RecordData)>> {
     let params = PARAMS.get().expect("params is not initialized");
 
-    let processed_records = process_csv_records(record, &params)?;
-    
-    let result: Vec<(Option<RecordData>, RecordData)> = processed_records
-        .into_iter()
-        .map(|opt| match opt {
-            Some(record_data) => (Some(record_data.clone()), record_data),
-            None => (None, RecordData::from(vec![])),
-        })
-        .collect();
+    let result = match params.mode {
+        Mode::Batch => process_csv_records(record, params)?,
+        Mode::Stream => {
+            let mut state = STREAM_STATE
+                .lock()
+                .map_err(|_| eyre!("stream state is poisoned"))?;
+            stream::process_csv_line(record, params, &mut state)?
+        }
+    };
 
     Ok(result)
 }
 
 
-/// Process CSV record and convert each row into an individual JSON record.
-fn process_csv_records(record: &SmartModuleRecord, params: &Params) -> Result<Vec<Option<RecordData>>> {
-    // Initialize CSV reader with the specified delimiter and other settings
-    let mut csv_reader = ReaderBuilder::new()
-        .delimiter(params.delimiter)
-        .has_headers(true)
-        .trim(Trim::All)
-        .from_reader(record.value.as_ref());
-
-    let mut rows: Vec<Option<RecordData>> = Vec::new();
-
-    // Collect headers and apply the header case transformation
-    let headers: Vec<String> = csv_reader
-        .headers()?
-        .iter()
-        .map(|h| match params.header_case {
-            HeaderCase::Camel => h.to_lower_camel_case(),
-            HeaderCase::Snake => h.to_snake_case(),
-            HeaderCase::None => h.to_string(),
-        })
-        .collect();
-
-    // Collect all records as Vec<Vec<String>>
-    let records: Vec<Vec<String>> = csv_reader.records()
-        .map(|record| record.unwrap().iter().map(|s| s.to_string()).collect())
-        .collect();
-
-    // Iterate over all records and convert each to a JSON object
-    for record in records.iter() {
-        let json_object: Value = headers
-            .iter()
-            .zip(record.iter())
-            .map(|(key, value)| (key.clone(), json!(value)))
-            .collect();
+/// Process CSV record and convert each row into an individual keyed JSON record.
+fn process_csv_records(
+    record: &SmartModuleRecord,
+    params: &Params,
+) -> Result<Vec<(Option<RecordData>, RecordData)>> {
+    let decoded = params.csv.encoding.decode(record.value.as_ref())?;
 
-        // Serialize the JSON object for this row and wrap it in RecordData
-        let serialized_row = serde_json::to_vec(&json_object)?;
+    // Initialize CSV reader with the configured dialect
+    let input = params.csv.dialect.prepare(&decoded)?;
 
-        // Wrap in Option<RecordData> and push to rows
-        rows.push(Some(RecordData::from(serialized_row)));
+    // Sniff the delimiter and the header line of this record when asked to
+    let sniffed = params.sniffs().then(|| sniff::sniff(&input, &params.csv.dialect));
+
+    let mut csv_reader = csv_reader_builder(params, sniffed.as_ref())
+        .has_headers(params.csv.headers.in_first_row(sniffed.as_ref()))
+        .from_reader(input.as_ref());
+
+    let mut rows: Vec<(Option<RecordData>, RecordData)> = Vec::new();
+
+    // Resolve output columns from the headers, the header case and the schema
+    let headers = params
+        .csv
+        .headers
+        .resolve(
+            &params.csv.dialect.restore(csv_reader.headers()?.clone()),
+            sniffed.as_ref(),
+        );
+    let layout = RowLayout::new(&headers, &params.csv)?;
+
+    // Iterate over all records and convert each to a keyed JSON record
+    for (index, result) in csv_reader.records().enumerate() {
+        let row = match result.map(|row| params.csv.dialect.restore(row)) {
+            Ok(row) => row,
+            Err(err) => {
+                rows.extend(error_record(RowError::from_csv(err), params, &input)?);
+                continue;
+            }
+        };
+
+        // Rows rejected by the row filter are dropped before conversion
+        if !layout.matches(&row) {
+            continue;
+        }
+
+        let keyed_row = layout
+            .row_to_json(&row, &params.csv)
+            .and_then(|mut json_object| {
+                params.metadata.apply(
+                    &mut json_object,
+                    index + 1,
+                    record,
+                    sniffed.as_ref(),
+                    &params.csv.dialect,
+                )?;
+                keyed_record(json_object, &layout, params)
+            });
+        match keyed_row {
+            Ok(keyed_row) => rows.push(keyed_row),
+            Err(err) => rows.extend(error_record(RowError::at(&row, err), params, &input)?),
+        }
     }
 
     Ok(rows)
 }
 
+/// Apply the error policy to a failed row of the prepared input; emitted error objects
+/// carry no key.
+fn error_record(
+    error: RowError,
+    params: &Params,
+    input: &[u8],
+) -> Result<Option<(Option<RecordData>, RecordData)>> {
+    match params.csv.on_error.handle(error, input, &params.csv.dialect)? {
+        Some(error_object) => Ok(Some((None, RecordData::from(serde_json::to_vec(&error_object)?)))),
+        None => Ok(None),
+    }
+}
+
+/// CSV reader settings shared by the batch and stream modes.
+fn csv_reader_builder(params: &Params, sniffed: Option<&Sniffed>) -> ReaderBuilder {
+    let mut builder = params.csv.dialect.reader_builder();
+    builder.flexible(true);
+    if let Some(sniffed) = sniffed {
+        builder.delimiter(sniffed.delimiter);
+    }
+    builder
+}
+
+/// Serialize a JSON row and pair it with its record key.
+fn keyed_record(
+    json_object: Value,
+    layout: &RowLayout,
+    params: &Params,
+) -> Result<(Option<RecordData>, RecordData)> {
+    // Serialize the JSON object for this row and wrap it in RecordData
+    let serialized_row = serde_json::to_vec(&json_object)?;
+
+    // Build the record key from the row
+    let key = params.record_key.build(&json_object, layout, &serialized_row)?;
+    Ok((key, RecordData::from(serialized_row)))
+}
+
 #[smartmodule(init)]
 fn init(params: SmartModuleExtraParams) -> Result<()> {
-    let delimiter_param = params
-        .get(DELIMITER_PARAM_NAME)
-        .map_or(DEFAULT_DELIMITER, |v| {
-            v.chars().next().expect("delimiter is empty") as u8
-        });
+    let record_key_param = parse_record_key(&params)?;
 
-    let case_param = params
-        .get(HEADER_CASE_PARAM_NAME)
-        .map_or(HeaderCase::None, |v| {
-            v.to_string().try_into().unwrap_or_else(|e| {
-                panic!("failed to parse header case: {}", e);
-            })
-        });
+    let mode_param = match params.get(MODE_PARAM_NAME) {
+        Some(v) => Mode::try_from(v.to_string()).map_err(|e| eyre!("failed to parse mode: {}", e))?,
+        None => Mode::Batch,
+    };
+
+    let header_pattern_param = match params.get(HEADER_PATTERN_PARAM_NAME) {
+        Some(v) => Some(
+            Regex::new(v).map_err(|err| eyre!("cannot parse `header_pattern` param: {}", err))?,
+        ),
+        None => None,
+    };
+
+    let header_repeats_param = match params.get(HEADER_REPEATS_PARAM_NAME) {
+        Some(v) => parse_bool(HEADER_REPEATS_PARAM_NAME, v)?,
+        None => false,
+    };
+
+    let metadata_param = parse_metadata(&params)?;
+
+    let params = Params {
+        metadata: metadata_param,
+        record_key: record_key_param,
+        mode: mode_param,
+        header_pattern: header_pattern_param,
+        header_repeats: header_repeats_param,
+        ..Params::from(CsvParams::from_params(&params)?)
+    };
+
+    // Sniffing needs the leading lines of a whole file, stream records hold a single line
+    if params.sniffs() && matches!(params.mode, Mode::Stream) {
+        return Err(eyre!(
+            "`delimiter=auto`, `headers=detect` and `dialect_field` cannot be used with `mode=stream`"
+        ));
+    }
 
-    PARAMS
-        .set(Params::new(delimiter_param, case_param))
-        .expect("params is already initialized");
+    PARAMS.set(params).expect("params is already initialized");
 
     Ok(())
 }
 
-#[derive(Debug)]
+/// Resolve the record key from the `record_key`, `key_columns` and `key_separator` params.
+fn parse_record_key(params: &SmartModuleExtraParams) -> Result<RecordKey> {
+    let record_key = params.get(RECORD_KEY_PARAM_NAME).map(|v| v.to_lowercase());
+    let key_columns = params.get(KEY_COLUMNS_PARAM_NAME);
+
+    match (record_key.as_deref(), key_columns) {
+        (None | Some("columns"), Some(columns)) => Ok(RecordKey::Columns {
+            columns: parse_list(KEY_COLUMNS_PARAM_NAME, columns)?,
+            separator: params
+                .get(KEY_SEPARATOR_PARAM_NAME)
+                .map_or(DEFAULT_KEY_SEPARATOR.to_string(), |v| v.to_string()),
+        }),
+        (Some("columns"), None) => Err(eyre!("`record_key=columns` requires the `key_columns` param")),
+        (_, Some(_)) => Err(eyre!("`key_columns` param can only be used with `record_key=columns`")),
+        (None | Some("row"), None) => Ok(RecordKey::Row),
+        (Some("none"), None) => Ok(RecordKey::None),
+        (Some(other), None) => Err(eyre!(
+            "invalid `record_key` param: expected `row`, `columns` or `none`, got `{}`",
+            other
+        )),
+    }
+}
+
+/// Parse the row metadata params; each field is added only when its param is set.
+fn parse_metadata(params: &SmartModuleExtraParams) -> Result<Metadata> {
+    let field = |name: &str| match params.get(name) {
+        Some(v) if v.is_empty() => Err(eyre!("`{}` param cannot be empty", name)),
+        Some(v) => Ok(Some(v.to_string())),
+        None => Ok(None),
+    };
+
+    let source = match (params.get(SOURCE_PARAM_NAME), field(SOURCE_FIELD_PARAM_NAME)?) {
+        (Some(tag), source_field) => Some((
+            source_field.unwrap_or_else(|| DEFAULT_SOURCE_FIELD.to_string()),
+            tag.to_string(),
+        )),
+        (None, Some(_)) => return Err(eyre!("`source_field` param requires `source`")),
+        (None, None) => None,
+    };
+
+    Ok(Metadata {
+        row_number_field: field(ROW_NUMBER_FIELD_PARAM_NAME)?,
+        offset_field: field(OFFSET_FIELD_PARAM_NAME)?,
+        timestamp_field: field(TIMESTAMP_FIELD_PARAM_NAME)?,
+        source,
+        dialect_field: field(DIALECT_FIELD_PARAM_NAME)?,
+    })
+}
+
+/// Params of the SmartModule; the CSV params are shared with csv-json-array.
+#[derive(Debug, Default)]
 struct Params {
-    delimiter: u8,
-    header_case: HeaderCase,
+    csv: CsvParams,
+    record_key: RecordKey,
+    mode: Mode,
+    header_pattern: Option<Regex>,
+    header_repeats: bool,
+    metadata: Metadata,
 }
 
-#[derive(Debug)]
-enum HeaderCase {
-    Camel,
-    Snake,
-    None,
+/// Whether each record holds a whole CSV file or a single line of a stream.
+#[derive(Debug, Default)]
+enum Mode {
+    #[default]
+    Batch,
+    Stream,
 }
 
-impl TryFrom<String> for HeaderCase {
+impl TryFrom<String> for Mode {
     type Error = &'static str;
     fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
         match s.to_lowercase().as_str() {
-            "camel" => Ok(HeaderCase::Camel),
-            "snake" => Ok(HeaderCase::Snake),
-            "" | "none" => Ok(HeaderCase::None),
-            _ => Err("Invalid header case"),
+            "" | "batch" => Ok(Mode::Batch),
+            "stream" => Ok(Mode::Stream),
+            _ => Err("Invalid mode"),
         }
     }
 }
 
 impl Params {
-    fn new(delimiter: u8, header_case: HeaderCase) -> Self {
+    /// Whether each record is sniffed before it is read.
+    fn sniffs(&self) -> bool {
+        self.csv.sniffs() || self.metadata.dialect_field.is_some()
+    }
+}
+
+impl From<CsvParams> for Params {
+    fn from(csv: CsvParams) -> Self {
         Self {
-            delimiter,
-            header_case,
+            csv,
+            ..Self::default()
         }
     }
 }
@@ -136,18 +299,25 @@ impl Params {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use csv_common::case::HeaderCase;
+    use csv_common::dialect::Dialect;
+    use csv_common::encoding::InputEncoding;
+    use csv_common::error::OnError;
+    use csv_common::filter::{Projection, RowFilter};
+    use csv_common::params::{parse_header_map, Headers, RaggedRows, DEFAULT_DELIMITER};
+    use csv_common::schema::Schema;
     use fluvio_smartmodule::{Record, RecordData, SmartModuleRecord};
     use fluvio_smartmodule::dataplane::record::RecordHeader;
-    use serde_json::json;
+    use serde_json::{json, Value};
 
     // Utility function to convert Vec<Option<RecordData>> to Vec<u8>
-    fn convert_records_to_json(result: Vec<Option<RecordData>>) -> Vec<u8> {
+    fn convert_records_to_json(result: Vec<(Option<RecordData>, RecordData)>) -> Vec<u8> {
         let result_json: Vec<Value> = result
             .into_iter()
-            .filter_map(|record| record.map(|r| {
+            .map(|(_, r)| {
                 // Convert RecordData into JSON object
-                serde_json::from_slice::<Value>(&r.to_vec()).unwrap()
-            }))
+                serde_json::from_slice::<Value>(r.as_ref()).unwrap()
+            })
             .collect();
     
         serde_json::to_vec(&result_json).unwrap()
@@ -164,7 +334,7 @@ mod tests {
         let record = SmartModuleRecord::new(
             Record::new(csv_data), 0, 0
         );
-        let params = Params::new(DEFAULT_DELIMITER, HeaderCase::None);
+        let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, HeaderCase::None));
 
         // Test Conversion
         let computed = process_csv_records(&record, &params).unwrap();
@@ -193,7 +363,7 @@ mod tests {
             headers: 0,
         };
         let record = SmartModuleRecord::new(data_record, 0, 0);
-        let params = Params::new(DEFAULT_DELIMITER, HeaderCase::Camel);
+        let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Camel));
 
         // Test Conversion
         let computed = process_csv_records(&record, &params).unwrap();
@@ -219,7 +389,7 @@ mod tests {
             headers: 0,
         };
         let record = SmartModuleRecord::new(data_record, 0, 0);
-        let params = Params::new(DEFAULT_DELIMITER, HeaderCase::Snake);
+        let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake));
 
         // Test Conversion
         let computed = process_csv_records(&record, &params).unwrap();
@@ -245,7 +415,7 @@ mod tests {
             headers: 0,
         };
         let record = SmartModuleRecord::new(data_record, 0, 0);
-        let params = Params::new(DEFAULT_DELIMITER, HeaderCase::None);
+        let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, HeaderCase::None));
 
         // Test Conversion
         let computed = process_csv_records(&record, &params).unwrap();
@@ -256,5 +426,624 @@ mod tests {
         let expected_output = serde_json::to_vec(&expected_json).unwrap();
 
         assert_eq!(computed_output, expected_output);
-    }    
+    }
+
+    #[test]
+    fn test_infer_types_conversion() {
+        let csv_data = "LocationID,Zip,Rate,Active,Note\n1,02134,4.5,true,\n2,07030,-0.25,FALSE,late\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            infer_types: true,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"LocationID": 1, "Zip": "02134", "Rate": 4.5, "Active": true, "Note": null}),
+            json!({"LocationID": 2, "Zip": "07030", "Rate": -0.25, "Active": false, "Note": "late"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+        fn test_schema_conversion() {
+        let csv_data = "LocationID,Borough,Zone\n1,EWR,Newark Airport\n2,,Jamaica Bay\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let schema = r#"[
+            {"column": "LocationID", "type": "integer", "rename": "location_id", "required": true},
+            {"column": "Borough", "default": "Unknown"},
+            {"column": "service_zone", "default": "N/A"}
+        ]"#;
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(schema).unwrap(),
+            ..CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake)
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"location_id": 1, "borough": "EWR", "zone": "Newark Airport", "service_zone": "N/A"}),
+            json!({"location_id": 2, "borough": "Unknown", "zone": "Jamaica Bay", "service_zone": "N/A"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_schema_rejects_invalid_value() {
+        let csv_data = "id,name\n1,Alice\nx,Bob\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(r#"[{"column": "id", "type": "integer"}]"#).unwrap(),
+            ..CsvParams::default()
+        });
+
+        let err = process_csv_records(&record, &params).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, byte 16: column `id`: cannot convert `x` to integer"
+        );
+    }
+
+    #[test]
+    fn test_record_key_from_columns() {
+        let csv_data = "LocationID,Borough,Zone\n1,EWR,Newark Airport\n2,Queens,Jamaica Bay\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params {
+            record_key: RecordKey::Columns {
+                columns: vec!["borough".to_string(), "location_id".to_string()],
+                separator: "/".to_string(),
+            },
+            ..Params::from(CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake))
+        };
+
+        let computed = process_csv_records(&record, &params).unwrap();
+        let keys: Vec<Option<Vec<u8>>> = computed
+            .into_iter()
+            .map(|(key, _)| key.map(|k| k.as_ref().to_vec()))
+            .collect();
+
+        assert_eq!(keys, vec![Some(b"EWR/1".to_vec()), Some(b"Queens/2".to_vec())]);
+    }
+
+    #[test]
+    fn test_record_key_none() {
+        let csv_data = "name,age\nAlice,30\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params {
+            record_key: RecordKey::None,
+            ..Params::default()
+        };
+
+        let computed = process_csv_records(&record, &params).unwrap();
+
+        assert_eq!(computed.len(), 1);
+        assert!(computed[0].0.is_none());
+    }
+
+    #[test]
+    fn test_parse_record_key_params() {
+        let params: SmartModuleExtraParams = std::collections::BTreeMap::from([
+            ("key_columns".to_string(), "borough, zone".to_string()),
+        ])
+        .into();
+        let RecordKey::Columns { columns, separator } = parse_record_key(&params).unwrap() else {
+            panic!("expected columns key");
+        };
+        assert_eq!(columns, vec!["borough", "zone"]);
+        assert_eq!(separator, DEFAULT_KEY_SEPARATOR);
+
+        let params: SmartModuleExtraParams = std::collections::BTreeMap::from([
+            ("record_key".to_string(), "none".to_string()),
+            ("key_columns".to_string(), "borough".to_string()),
+        ])
+        .into();
+        assert!(parse_record_key(&params).is_err());
+    }
+
+    #[test]
+    fn test_headerless_auto_names() {
+        let csv_data = "1,EWR,Newark Airport\n2,Queens,Jamaica Bay\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            headers: Headers::Auto,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"col_0": "1", "col_1": "EWR", "col_2": "Newark Airport"}),
+            json!({"col_0": "2", "col_1": "Queens", "col_2": "Jamaica Bay"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_headerless_explicit_names_with_case() {
+        let csv_data = "1,EWR,Newark Airport\n2,Queens,Jamaica Bay\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            headers: Headers::parse("LocationID,Borough,Zone").unwrap(),
+            ..CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake)
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"location_id": "1", "borough": "EWR", "zone": "Newark Airport"}),
+            json!({"location_id": "2", "borough": "Queens", "zone": "Jamaica Bay"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_on_error_skip() {
+        let csv_data = "name,age\nAlice,30\nBob,25,extra\nCharlie,35\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            on_error: OnError::Skip,
+            ragged_rows: RaggedRows::Strict,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"name": "Alice", "age": "30"}),
+            json!({"name": "Charlie", "age": "35"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_on_error_emit() {
+        let csv_data = "id,name\n1,Alice\nx,Bob\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(r#"[{"column": "id", "type": "integer"}]"#).unwrap(),
+            on_error: OnError::Emit,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        assert!(computed[1].0.is_none());
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"id": 1, "name": "Alice"}),
+            json!({
+                "error": "column `id`: cannot convert `x` to integer",
+                "line": 3,
+                "byte": 16,
+                "raw": "x,Bob",
+            }),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_on_error_fail() {
+        let csv_data = "name,age\nAlice,30\nBob\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            ragged_rows: RaggedRows::Strict,
+            ..CsvParams::default()
+        });
+
+        let err = process_csv_records(&record, &params).unwrap_err();
+        assert_eq!(err.to_string(), "line 3, byte 18: found 1 fields, expected 2");
+    }
+
+    #[test]
+    fn test_on_error_missing_key_column() {
+        let csv_data = "name,age\nAlice,30\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params {
+            record_key: RecordKey::Columns {
+                columns: vec!["id".to_string()],
+                separator: ":".to_string(),
+            },
+            ..Params::from(CsvParams {
+                on_error: OnError::Emit,
+                ..CsvParams::default()
+            })
+        };
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![json!({
+            "error": "key column `id` is not in the row",
+            "line": 2,
+            "byte": 9,
+            "raw": "Alice,30",
+        })];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_ragged_rows_fill() {
+        let csv_data = "id,name,zone\n1,Alice\n2,Bob,Queens,late,7\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            missing_value: json!("n/a"),
+            extra_field: "overflow".to_string(),
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"id": "1", "name": "Alice", "zone": "n/a"}),
+            json!({"id": "2", "name": "Bob", "zone": "Queens", "overflow": ["late", "7"]}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_extra_field_collides_with_column() {
+        let csv_data = "id,_extra\n1,note\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+
+        let err = process_csv_records(&record, &Params::default()).unwrap_err();
+        assert_eq!(err.to_string(), "extra field `_extra` collides with a column");
+
+        // Strict rows have no extra cells
+        let params = Params::from(CsvParams {
+            ragged_rows: RaggedRows::Strict,
+            ..CsvParams::default()
+        });
+        assert!(process_csv_records(&record, &params).is_ok());
+    }
+
+    #[test]
+    fn test_ragged_rows_schema_default() {
+        let csv_data = "id,name,zone\n1,Alice\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(r#"[{"column": "zone", "default": "Unknown"}]"#).unwrap(),
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![json!({"id": "1", "name": "Alice", "zone": "Unknown"})];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_multi_byte_dialect() {
+        let csv_data = "// exported\nid::note\n1::\"a::b\"\nx::c\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            dialect: Dialect::from_params(Some(r#"{"delimiter": "::", "comment": "//"}"#), None)
+                .unwrap(),
+            schema: Schema::parse(r#"[{"column": "id", "type": "integer"}]"#).unwrap(),
+            on_error: OnError::Emit,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"id": 1, "note": "a::b"}),
+            json!({
+                "error": "column `id`: cannot convert `x` to integer",
+                "line": 4,
+                "byte": 31,
+                "raw": "x::c",
+            }),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_unflatten_headers() {
+        let csv_data = "ID,Address.City,Address.Zip Code,Tags[0],Tags[1]\n1,Paris,75001,new,vip\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            unflatten: true,
+            ..CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake)
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![json!({
+            "id": "1",
+            "address": {"city": "Paris", "zip_code": "75001"},
+            "tags": ["new", "vip"],
+        })];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_unflatten_conflicting_headers() {
+        let csv_data = "a,a/b\n1,2\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            unflatten: true,
+            path_separator: "/".to_string(),
+            ..CsvParams::default()
+        });
+
+        let err = process_csv_records(&record, &params).unwrap_err();
+        assert_eq!(err.to_string(), "conflicting columns `a` and `a/b`");
+    }
+
+    #[test]
+    fn test_auto_encoding_utf16() {
+        let mut csv_data = vec![0xFF, 0xFE];
+        csv_data.extend("city,price\nZürich,€12\n".encode_utf16().flat_map(u16::to_le_bytes));
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            encoding: InputEncoding::Auto,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![json!({"city": "Zürich", "price": "€12"})];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_utf8_bom_headerless() {
+        let csv_data = "\u{feff}1,EWR\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            headers: Headers::Auto,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![json!({"col_0": "1", "col_1": "EWR"})];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_exclude_columns_and_filter() {
+        let csv_data = "id,name,status\n1,a,active\n2,b,closed\n3,c,active\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            projection: Projection::Exclude(vec!["status".to_string()]),
+            filter: RowFilter::parse(r#"{"column": "status", "op": "ne", "value": "closed"}"#)
+                .unwrap(),
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"id": "1", "name": "a"}),
+            json!({"id": "3", "name": "c"}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_extended_header_cases() {
+        let csv_data = "first name,zip_code\nAlice,75001\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+
+        for (case, expected_json) in [
+            (HeaderCase::Pascal, json!({"FirstName": "Alice", "ZipCode": "75001"})),
+            (HeaderCase::Kebab, json!({"first-name": "Alice", "zip-code": "75001"})),
+            (HeaderCase::Constant, json!({"FIRST_NAME": "Alice", "ZIP_CODE": "75001"})),
+            (HeaderCase::Cobol, json!({"FIRST-NAME": "Alice", "ZIP-CODE": "75001"})),
+        ] {
+            let params = Params::from(CsvParams::new(DEFAULT_DELIMITER, case));
+
+            // Test Conversion
+            let computed = process_csv_records(&record, &params).unwrap();
+            let computed_output = convert_records_to_json(computed);
+
+            // Prepare Result
+            let expected_output = serde_json::to_vec(&vec![expected_json]).unwrap();
+
+            assert_eq!(computed_output, expected_output);
+        }
+    }
+
+    #[test]
+    fn test_header_map_and_duplicate_headers() {
+        let csv_data = "Zone,Zone Name,zone-name,---,Borough\nEWR,Newark,N,x,Queens\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let params = Params::from(CsvParams {
+            header_map: parse_header_map(r#"{"Borough": "BoroughName", "Zone": "zone_name"}"#)
+                .unwrap(),
+            ..CsvParams::new(DEFAULT_DELIMITER, HeaderCase::Snake)
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![json!({
+            "zone_name": "EWR",
+            "zone_name_1": "Newark",
+            "zone_name_2": "N",
+            "_1": "x",
+            "BoroughName": "Queens",
+        })];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_row_metadata() {
+        let csv_data = "name,status\nAlice,active\nBob,closed\nCarl,active\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 120, 1700000000000);
+        let params = Params {
+            metadata: Metadata {
+                row_number_field: Some("_row".to_string()),
+                offset_field: Some("_offset".to_string()),
+                timestamp_field: Some("_timestamp".to_string()),
+                source: Some(("_source".to_string(), "crm/export.csv".to_string())),
+                ..Metadata::default()
+            },
+            ..Params::from(CsvParams {
+                filter: RowFilter::parse(r#"{"column": "status", "op": "eq", "value": "active"}"#)
+                    .unwrap(),
+                ..CsvParams::default()
+            })
+        };
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({
+                "name": "Alice", "status": "active", "_row": 1, "_offset": 120,
+                "_timestamp": 1700000000000i64, "_source": "crm/export.csv",
+            }),
+            json!({
+                "name": "Carl", "status": "active", "_row": 3, "_offset": 120,
+                "_timestamp": 1700000000000i64, "_source": "crm/export.csv",
+            }),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_schema_datetime() {
+        let csv_data = "id,created\n1,03/04/2024 07:00\n2,1709553600\n3,yesterday\n";
+        let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+        let schema = r#"[{
+            "column": "created",
+            "datetime": {
+                "formats": ["%m/%d/%Y %H:%M", "epoch_seconds"],
+                "timezone": "America/New_York",
+                "output": "epoch_millis"
+            }
+        }]"#;
+        let params = Params::from(CsvParams {
+            schema: Schema::parse(schema).unwrap(),
+            on_error: OnError::Skip,
+            ..CsvParams::default()
+        });
+
+        // Test Conversion
+        let computed = process_csv_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"id": "1", "created": 1709553600000i64}),
+            json!({"id": "2", "created": 1709553600000i64}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_sniffed_dialect() {
+        let params = Params {
+            metadata: Metadata {
+                dialect_field: Some("_dialect".to_string()),
+                ..Metadata::default()
+            },
+            ..Params::from(CsvParams {
+                dialect: Dialect::from_params(None, Some("auto")).unwrap(),
+                headers: Headers::Detect,
+                ..CsvParams::default()
+            })
+        };
+        let cases = [
+            (
+                "name;score\nAlice;1,5\n",
+                json!({"name": "Alice", "score": "1,5", "_dialect": {"delimiter": ";", "header": true}}),
+            ),
+            (
+                "EWR|Newark\nJFK|Queens\n",
+                json!({"col_0": "EWR", "col_1": "Newark", "_dialect": {"delimiter": "|", "header": false}}),
+            ),
+        ];
+
+        for (csv_data, expected_first_row) in cases {
+            let record = SmartModuleRecord::new(Record::new(csv_data), 0, 0);
+
+            // Test Conversion
+            let computed = process_csv_records(&record, &params).unwrap();
+            let first_row: Value = serde_json::from_slice(computed[0].1.as_ref()).unwrap();
+
+            assert_eq!(first_row, expected_first_row);
+        }
+    }
 }
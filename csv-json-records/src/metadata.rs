@@ -0,0 +1,123 @@
+use fluvio_smartmodule::{eyre, Result, SmartModuleRecord};
+use serde_json::{json, Map, Value};
+
+use csv_common::dialect::Dialect;
+use csv_common::sniff::Sniffed;
+
+/// Optional fields added to each JSON row to trace it back to its source.
+#[derive(Debug, Default)]
+pub(crate) struct Metadata {
+    /// Field holding the 1-based number of the data row.
+    pub(crate) row_number_field: Option<String>,
+    /// Field holding the offset of the source record.
+    pub(crate) offset_field: Option<String>,
+    /// Field holding the timestamp of the source record.
+    pub(crate) timestamp_field: Option<String>,
+    /// Field name and value of the static source tag.
+    pub(crate) source: Option<(String, String)>,
+    /// Field holding the dialect sniffed from the source record.
+    pub(crate) dialect_field: Option<String>,
+}
+
+impl Metadata {
+    /// Add the configured fields to a JSON row.
+    ///
+    /// Fails if a field is already set by a column, so metadata never overwrites data.
+    pub(crate) fn apply(
+        &self,
+        row: &mut Value,
+        row_number: usize,
+        record: &SmartModuleRecord,
+        sniffed: Option<&Sniffed>,
+        dialect: &Dialect,
+    ) -> Result<()> {
+        let Value::Object(fields) = row else {
+            return Ok(());
+        };
+
+        if let Some(field) = &self.row_number_field {
+            insert_new(fields, field, Value::from(row_number))?;
+        }
+        if let Some(field) = &self.offset_field {
+            insert_new(fields, field, Value::from(record.offset()))?;
+        }
+        if let Some(field) = &self.timestamp_field {
+            insert_new(fields, field, Value::from(record.timestamp()))?;
+        }
+        if let Some((field, tag)) = &self.source {
+            insert_new(fields, field, Value::String(tag.clone()))?;
+        }
+        if let (Some(field), Some(sniffed)) = (&self.dialect_field, sniffed) {
+            let dialect = json!({
+                "delimiter": dialect.restore_text(&char::from(sniffed.delimiter).to_string()),
+                "header": sniffed.has_header,
+            });
+            insert_new(fields, field, dialect)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn insert_new(fields: &mut Map<String, Value>, field: &str, value: Value) -> Result<()> {
+    if fields.contains_key(field) {
+        return Err(eyre!("metadata field `{}` collides with a column", field));
+    }
+    fields.insert(field.to_string(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluvio_smartmodule::Record;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_metadata() {
+        let metadata = Metadata {
+            row_number_field: Some("_row".to_string()),
+            offset_field: Some("_offset".to_string()),
+            timestamp_field: Some("_timestamp".to_string()),
+            source: Some(("_source".to_string(), "zones.csv".to_string())),
+            dialect_field: Some("_dialect".to_string()),
+        };
+        let sniffed = Sniffed {
+            delimiter: b'\t',
+            has_header: true,
+        };
+        let record = SmartModuleRecord::new(Record::new("a\n1\n"), 40, 1700000000000);
+        let mut row = json!({"a": "1"});
+
+        metadata
+            .apply(&mut row, 3, &record, Some(&sniffed), &Dialect::default())
+            .unwrap();
+
+        assert_eq!(
+            row,
+            json!({
+                "a": "1",
+                "_row": 3,
+                "_offset": 40,
+                "_timestamp": 1700000000000i64,
+                "_source": "zones.csv",
+                "_dialect": {"delimiter": "\t", "header": true},
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_collision() {
+        let metadata = Metadata {
+            row_number_field: Some("id".to_string()),
+            ..Metadata::default()
+        };
+        let record = SmartModuleRecord::new(Record::new("id\n1\n"), 0, 0);
+        let mut row = json!({"id": "1"});
+
+        let err = metadata
+            .apply(&mut row, 1, &record, None, &Dialect::default())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "metadata field `id` collides with a column");
+    }
+}
@@ -0,0 +1,295 @@
+use std::borrow::Cow;
+use std::sync::Mutex;
+
+use csv::StringRecord;
+use csv_common::error::RowError;
+use csv_common::params::CsvParams;
+use csv_common::row::RowLayout;
+use fluvio_smartmodule::{RecordData, Result, SmartModuleRecord};
+
+use crate::{csv_reader_builder, error_record, keyed_record, Params};
+
+/// Header captured from an earlier record in `stream` mode.
+pub(crate) static STREAM_STATE: Mutex<StreamState<'static>> = Mutex::new(StreamState {
+    header: None,
+    layout: None,
+    rows: 0,
+});
+
+#[derive(Default)]
+pub(crate) struct StreamState<'a> {
+    header: Option<StringRecord>,
+    /// Layout of the last headers used, rebuilt when the headers change.
+    layout: Option<(StringRecord, RowLayout<'a>)>,
+    /// Data rows seen since the header was captured.
+    rows: usize,
+}
+
+impl<'a> StreamState<'a> {
+    /// Layout for the headers, reusing the previous one when the headers are unchanged.
+    fn layout(&mut self, headers: StringRecord, params: &'a CsvParams) -> Result<&RowLayout<'a>> {
+        let stale = self
+            .layout
+            .as_ref()
+            .is_none_or(|(current, _)| *current != headers);
+        if stale {
+            let layout = RowLayout::new(&headers, params)?;
+            self.layout = Some((headers, layout));
+        }
+        Ok(&self.layout.as_ref().expect("layout is built").1)
+    }
+}
+
+/// Process a record holding one CSV line, using the header captured from earlier records.
+///
+/// The first line, or a record whose first line matches `header_pattern` when set, is
+/// captured as the header and produces no output. With `header_repeats`, a line equal to
+/// the captured header is a header line too. Lines seen before any header are dropped.
+pub(crate) fn process_csv_line<'a>(
+    record: &SmartModuleRecord,
+    params: &'a Params,
+    state: &mut StreamState<'a>,
+) -> Result<Vec<(Option<RecordData>, RecordData)>> {
+    let decoded = params.csv.encoding.decode(record.value.as_ref())?;
+
+    let first_line_matches = params
+        .header_pattern
+        .as_ref()
+        .is_some_and(|pattern| pattern.is_match(&first_line(&decoded, &params.csv)));
+
+    let input = params.csv.dialect.prepare(&decoded)?;
+    let mut csv_reader = csv_reader_builder(params, None)
+        .has_headers(false)
+        .from_reader(input.as_ref());
+
+    let mut rows: Vec<(Option<RecordData>, RecordData)> = Vec::new();
+
+    for (index, result) in csv_reader.records().enumerate() {
+        let row = match result.map(|row| params.csv.dialect.restore(row)) {
+            Ok(row) => row,
+            Err(err) => {
+                state.rows += 1;
+                rows.extend(error_record(RowError::from_csv(err), params, &input)?);
+                continue;
+            }
+        };
+
+        // Only the row starting the record can be a header line
+        let is_header_line = first_line_matches && index == 0;
+
+        let headers = if params.csv.headers.in_first_row(None) {
+            let awaiting_first_header = state.header.is_none() && params.header_pattern.is_none();
+            let repeated_header = params.header_repeats && state.header.as_ref() == Some(&row);
+            if is_header_line || awaiting_first_header || repeated_header {
+                state.header = Some(row);
+                state.rows = 0;
+                continue;
+            }
+            match &state.header {
+                Some(headers) => headers.clone(),
+                None => continue,
+            }
+        } else {
+            // Explicit or generated names never come from the stream
+            if is_header_line {
+                continue;
+            }
+            params.csv.headers.resolve(&row, None)
+        };
+
+        state.rows += 1;
+        let rows_seen = state.rows;
+
+        let layout = state.layout(headers, &params.csv)?;
+        if !layout.matches(&row) {
+            continue;
+        }
+        let keyed_row = layout
+            .row_to_json(&row, &params.csv)
+            .and_then(|mut json_object| {
+                params.metadata.apply(
+                    &mut json_object,
+                    rows_seen,
+                    record,
+                    None,
+                    &params.csv.dialect,
+                )?;
+                keyed_record(json_object, layout, params)
+            });
+        match keyed_row {
+            Ok(keyed_row) => rows.push(keyed_row),
+            Err(err) => rows.extend(error_record(RowError::at(&row, err), params, &input)?),
+        }
+    }
+
+    Ok(rows)
+}
+
+/// First line of the record, which `header_pattern` is matched against.
+fn first_line<'a>(value: &'a [u8], params: &CsvParams) -> Cow<'a, str> {
+    let line = value
+        .split(|byte| params.dialect.is_terminator(*byte))
+        .next()
+        .unwrap_or_default();
+    String::from_utf8_lossy(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Metadata;
+    use csv_common::params::Headers;
+    use fluvio_smartmodule::Record;
+    use regex::Regex;
+    use serde_json::{json, Value};
+
+    fn process_lines(lines: &[&str], params: &Params) -> Vec<Value> {
+        let mut state = StreamState::default();
+        lines
+            .iter()
+            .flat_map(|line| {
+                let record = SmartModuleRecord::new(Record::new(line.to_string()), 0, 0);
+                process_csv_line(&record, params, &mut state).unwrap()
+            })
+            .map(|(_, value)| serde_json::from_slice(value.as_ref()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_header_from_first_record() {
+        let params = Params::default();
+
+        let rows = process_lines(&["name,age\n", "Alice,30\n", "Bob,25"], &params);
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({"name": "Alice", "age": "30"}),
+                json!({"name": "Bob", "age": "25"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeated_header_is_data_by_default() {
+        let params = Params::default();
+
+        let rows = process_lines(&["name,age", "Alice,30", "name,age"], &params);
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({"name": "Alice", "age": "30"}),
+                json!({"name": "name", "age": "age"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header_repeats() {
+        let params = Params {
+            header_repeats: true,
+            ..Params::default()
+        };
+
+        let rows = process_lines(
+            &["name,age", "Alice,30", "name,age", "Bob,25"],
+            &params,
+        );
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({"name": "Alice", "age": "30"}),
+                json!({"name": "Bob", "age": "25"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header_pattern_rearms() {
+        let params = Params {
+            header_pattern: Some(Regex::new("^(name|id),").unwrap()),
+            ..Params::default()
+        };
+
+        let rows = process_lines(
+            &["Orphan,1", "name,age", "Alice,30", "id,score", "7,99.5"],
+            &params,
+        );
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({"name": "Alice", "age": "30"}),
+                json!({"id": "7", "score": "99.5"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header_pattern_matches_first_line_only() {
+        let params = Params {
+            header_pattern: Some(Regex::new(",age$").unwrap()),
+            ..Params::default()
+        };
+
+        let rows = process_lines(&["name,age\nAlice,30", "Bob,25\nEve,age"], &params);
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({"name": "Alice", "age": "30"}),
+                json!({"name": "Bob", "age": "25"}),
+                json!({"name": "Eve", "age": "age"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explicit_headers_skip_header_lines() {
+        let params = Params {
+            header_pattern: Some(Regex::new("^id,").unwrap()),
+            ..Params::from(CsvParams {
+                headers: Headers::Names(vec!["id".to_string(), "score".to_string()]),
+                ..CsvParams::default()
+            })
+        };
+
+        let rows = process_lines(&["id,score", "7,99.5", "8,12"], &params);
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({"id": "7", "score": "99.5"}),
+                json!({"id": "8", "score": "12"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_row_numbers_restart_with_header() {
+        let params = Params {
+            header_pattern: Some(Regex::new("^name,").unwrap()),
+            metadata: Metadata {
+                row_number_field: Some("_row".to_string()),
+                ..Metadata::default()
+            },
+            ..Params::default()
+        };
+
+        let rows = process_lines(
+            &["name,age", "Alice,30", "Bob,25", "name,age", "Eve,41"],
+            &params,
+        );
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({"name": "Alice", "age": "30", "_row": 1}),
+                json!({"name": "Bob", "age": "25", "_row": 2}),
+                json!({"name": "Eve", "age": "41", "_row": 1}),
+            ]
+        );
+    }
+}
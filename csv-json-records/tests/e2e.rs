@@ -30,6 +30,38 @@ fn csv_with_transit_data() {
     );
 }
 
+#[test]
+fn csv_with_inferred_types() {
+    csv_cmd(
+        "test-typed",
+        "test-data/typed/output.json",
+    );
+}
+
+#[test]
+fn csv_without_header_line() {
+    csv_cmd(
+        "test-headerless",
+        "test-data/headerless/output.json",
+    );
+}
+
+#[test]
+fn csv_with_tab_dialect() {
+    csv_cmd(
+        "test-tab",
+        "test-data/tab/output.json",
+    );
+}
+
+#[test]
+fn csv_with_sniffed_dialect() {
+    csv_cmd(
+        "test-semicolon-auto",
+        "test-data/semicolon-snake/output.json",
+    );
+}
+
 fn remove_whitespace(s: &str) -> String {
     s.chars().filter(|c| !c.is_whitespace()).collect()
 }
@@ -0,0 +1,67 @@
+use csv_common::error::OnError;
+use fluvio_smartmodule::Result;
+use serde_json::{json, Value};
+
+/// A row that failed to convert, with its location in the workbook.
+#[derive(Debug)]
+pub(crate) struct RowError {
+    sheet: String,
+    /// 1-based row number, as shown by spreadsheet applications.
+    row: u64,
+    message: String,
+}
+
+impl RowError {
+    /// Error for the 0-based `row` of a sheet.
+    pub(crate) fn new(sheet: &str, row: u32, err: impl std::fmt::Display) -> Self {
+        Self {
+            sheet: sheet.to_string(),
+            row: u64::from(row) + 1,
+            message: err.to_string(),
+        }
+    }
+
+    /// Apply the `on_error` policy to the error.
+    ///
+    /// Returns the error object to output in place of the row, or `None` to drop it.
+    pub(crate) fn handle(self, on_error: OnError) -> Result<Option<Value>> {
+        let Self {
+            sheet,
+            row,
+            message,
+        } = self;
+        on_error.report(
+            message,
+            format_args!("sheet `{}`, row {}", sheet, row),
+            || json!({"sheet": sheet, "row": row}),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies() {
+        let error = || RowError::new("Expenses", 2, "cell D3 holds the error #DIV/0!");
+
+        let err = error().handle(OnError::Fail).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "sheet `Expenses`, row 3: cell D3 holds the error #DIV/0!"
+        );
+
+        assert_eq!(error().handle(OnError::Skip).unwrap(), None);
+
+        let emitted = error().handle(OnError::Emit).unwrap();
+        assert_eq!(
+            emitted,
+            Some(json!({
+                "error": "cell D3 holds the error #DIV/0!",
+                "sheet": "Expenses",
+                "row": 3,
+            }))
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::io::Cursor;
+use std::sync::OnceLock;
+
+use calamine::{open_workbook_auto_from_rs, Data, Reader};
+use csv_common::case::HeaderCase;
+use csv_common::error::OnError;
+use fluvio_smartmodule::{
+    dataplane::smartmodule::SmartModuleExtraParams, eyre, smartmodule, RecordData, Result,
+    SmartModuleRecord,
+};
+use serde_json::{Map, Value};
+
+mod error;
+mod sheet;
+
+use error::RowError;
+use sheet::{cell_name, cell_to_json, column_name, CellRange, SheetSelector};
+
+static PARAMS: OnceLock<Params> = OnceLock::new();
+const SHEET_PARAM_NAME: &str = "sheet";
+const RANGE_PARAM_NAME: &str = "range";
+const HEADER_CASE_PARAM_NAME: &str = "header_case";
+const ON_ERROR_PARAM_NAME: &str = "on_error";
+
+#[smartmodule(array_map)]
+pub fn array_map(record: &SmartModuleRecord) -> Result<Vec<(Option<RecordData>, RecordData)>> {
+    let params = PARAMS.get().expect("params is not initialized");
+
+    process_workbook(record, params)
+}
+
+/// Read the selected sheet of a workbook and convert each row into an individual JSON record.
+fn process_workbook(
+    record: &SmartModuleRecord,
+    params: &Params,
+) -> Result<Vec<(Option<RecordData>, RecordData)>> {
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(record.value.as_ref()))
+        .map_err(|e| eyre!("Failed to read workbook: {}", e))?;
+
+    let sheet_names = workbook.sheet_names();
+    let sheet = params.sheet.resolve(&sheet_names)?;
+    let cells = workbook
+        .worksheet_range(sheet)
+        .map_err(|e| eyre!("Failed to read sheet `{}`: {}", sheet, e))?;
+    let cells = match &params.range {
+        Some(range) => range.select(&cells),
+        None => cells,
+    };
+
+    let mut records = Vec::new();
+
+    // The first row of the range holds the headers
+    let Some((first_row, first_column)) = cells.start() else {
+        return Ok(records);
+    };
+    let mut rows = cells.rows();
+    let Some(header_row) = rows.next() else {
+        return Ok(records);
+    };
+    let headers = header_names(header_row, first_column, params);
+
+    for (index, row) in rows.enumerate() {
+        if row.iter().all(|cell| *cell == Data::Empty) {
+            continue;
+        }
+
+        let row_index = first_row + 1 + index as u32;
+        let json_object = match row_to_json(row, &headers, row_index, first_column) {
+            Ok(json_object) => json_object,
+            Err(err) => match RowError::new(sheet, row_index, err).handle(params.on_error)? {
+                Some(error_object) => error_object,
+                None => continue,
+            },
+        };
+        records.push((None, RecordData::from(serde_json::to_vec(&json_object)?)));
+    }
+
+    Ok(records)
+}
+
+/// Field names from the header row.
+///
+/// Empty header cells are named after their column letter. Names that repeat once the
+/// header case is applied get a `_1`, `_2`, ... suffix.
+fn header_names(header_row: &[Data], first_column: u32, params: &Params) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(header_row.len());
+    for (index, cell) in header_row.iter().enumerate() {
+        let column = first_column + index as u32;
+        let header = match cell_to_json(cell) {
+            Ok(Value::String(text)) if !text.trim().is_empty() => text.trim().to_string(),
+            Ok(Value::Null) | Ok(Value::String(_)) | Err(_) => column_name(column),
+            Ok(value) => value.to_string(),
+        };
+        let name = params.header_case.apply(&header);
+
+        let mut unique = name.clone();
+        let mut suffix = 0;
+        while names.contains(&unique) {
+            suffix += 1;
+            unique = format!("{}_{}", name, suffix);
+        }
+        names.push(unique);
+    }
+    names
+}
+
+/// Convert the cells of a data row into a JSON object keyed by the header names.
+fn row_to_json(
+    row: &[Data],
+    headers: &[String],
+    row_index: u32,
+    first_column: u32,
+) -> Result<Value> {
+    let mut json_object = Map::new();
+    for (index, (header, cell)) in headers.iter().zip(row).enumerate() {
+        let value = cell_to_json(cell).map_err(|err| {
+            let reference = cell_name((row_index, first_column + index as u32));
+            eyre!("cell {} holds the error {}", reference, err)
+        })?;
+        json_object.insert(header.clone(), value);
+    }
+    Ok(Value::Object(json_object))
+}
+
+#[smartmodule(init)]
+fn init(params: SmartModuleExtraParams) -> Result<()> {
+    let sheet_param = match params.get(SHEET_PARAM_NAME) {
+        Some(v) => SheetSelector::parse(v)?,
+        None => SheetSelector::First,
+    };
+
+    let range_param = match params.get(RANGE_PARAM_NAME) {
+        Some(v) => Some(CellRange::parse(v)?),
+        None => None,
+    };
+
+    let case_param = match params.get(HEADER_CASE_PARAM_NAME) {
+        Some(v) => HeaderCase::try_from(v.to_string())
+            .map_err(|e| eyre!("failed to parse header_case: {}", e))?,
+        None => HeaderCase::None,
+    };
+
+    let on_error_param = match params.get(ON_ERROR_PARAM_NAME) {
+        Some(v) => OnError::try_from(v.to_string())
+            .map_err(|e| eyre!("failed to parse on_error: {}", e))?,
+        None => OnError::Fail,
+    };
+
+    PARAMS
+        .set(Params {
+            sheet: sheet_param,
+            range: range_param,
+            header_case: case_param,
+            on_error: on_error_param,
+        })
+        .expect("params is already initialized");
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct Params {
+    sheet: SheetSelector,
+    range: Option<CellRange>,
+    header_case: HeaderCase,
+    on_error: OnError,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluvio_smartmodule::Record;
+
+    const BUDGET: &[u8] = include_bytes!("../test-data/budget/input.xlsx");
+    const INVENTORY: &[u8] = include_bytes!("../test-data/inventory/input.ods");
+
+    fn convert_records_to_json(records: Vec<(Option<RecordData>, RecordData)>) -> Vec<Value> {
+        records
+            .iter()
+            .map(|(_, data)| serde_json::from_slice(data.as_ref()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_sheet_and_range() {
+        let record = SmartModuleRecord::new(Record::new(BUDGET), 0, 0);
+        let params = Params {
+            sheet: SheetSelector::parse("Expenses").unwrap(),
+            range: Some(CellRange::parse("B3:F8").unwrap()),
+            header_case: HeaderCase::Snake,
+            ..Params::default()
+        };
+
+        // Test Conversion
+        let computed = process_workbook(&record, &params).unwrap();
+
+        // Prepare Result
+        let expected: Vec<Value> = include_str!("../test-data/budget/output.json")
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(convert_records_to_json(computed), expected);
+    }
+
+    #[test]
+    fn test_ods_workbook() {
+        let record = SmartModuleRecord::new(Record::new(INVENTORY), 0, 0);
+
+        // Test Conversion
+        let computed = process_workbook(&record, &Params::default()).unwrap();
+
+        // Prepare Result
+        let expected: Vec<Value> = include_str!("../test-data/inventory/output.json")
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(convert_records_to_json(computed), expected);
+    }
+
+    #[test]
+    fn test_error_cells() {
+        let record = SmartModuleRecord::new(Record::new(BUDGET), 0, 0);
+
+        let err = process_workbook(&record, &Params::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "sheet `Summary`, row 3: cell D3 holds the error #DIV/0!"
+        );
+
+        let params = Params {
+            on_error: OnError::Emit,
+            ..Params::default()
+        };
+        let computed = process_workbook(&record, &params).unwrap();
+        assert_eq!(
+            convert_records_to_json(computed),
+            vec![
+                serde_json::json!({"Quarter": "Q1", "Budget": 12000, "Spent": 3345.5, "Used": 0.27879166666666666}),
+                serde_json::json!({"error": "cell D3 holds the error #DIV/0!", "sheet": "Summary", "row": 3}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header_names() {
+        let header_row = [
+            Data::String("Amount".to_string()),
+            Data::Empty,
+            Data::String("amount".to_string()),
+            Data::Float(2024.0),
+        ];
+        let params = Params {
+            header_case: HeaderCase::Snake,
+            ..Params::default()
+        };
+
+        assert_eq!(
+            header_names(&header_row, 1, &params),
+            vec!["amount", "c", "amount_1", "2024"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_sheet() {
+        let record = SmartModuleRecord::new(Record::new(BUDGET), 0, 0);
+        let params = Params {
+            sheet: SheetSelector::parse("Income").unwrap(),
+            ..Params::default()
+        };
+
+        let err = process_workbook(&record, &params).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "sheet `Income` not found, the workbook has: Summary, Expenses"
+        );
+    }
+}
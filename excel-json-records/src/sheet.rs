@@ -0,0 +1,307 @@
+use calamine::{CellErrorType, Data, Range};
+use chrono::NaiveTime;
+use fluvio_smartmodule::{eyre, Result};
+use serde_json::{Number, Value};
+
+/// Largest integer a JSON consumer can read back from a double without losing precision.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+/// Number of rows of a worksheet in Excel and LibreOffice.
+const MAX_ROWS: u32 = 1_048_576;
+/// Number of columns of a worksheet, `A` to `XFD`.
+const MAX_COLUMNS: u32 = 16_384;
+
+/// Which worksheet of the workbook is converted.
+#[derive(Debug, Default)]
+pub(crate) enum SheetSelector {
+    /// The first sheet of the workbook.
+    #[default]
+    First,
+    /// A sheet name, or a 0-based index when no sheet has that name.
+    Name(String),
+}
+
+impl SheetSelector {
+    pub(crate) fn parse(value: &str) -> Result<Self> {
+        if value.is_empty() {
+            return Err(eyre!("`sheet` param cannot be empty"));
+        }
+        Ok(SheetSelector::Name(value.to_string()))
+    }
+
+    /// Find the selected sheet among the sheet names of a workbook.
+    pub(crate) fn resolve<'a>(&self, sheet_names: &'a [String]) -> Result<&'a str> {
+        let found = match self {
+            SheetSelector::First => sheet_names.first(),
+            SheetSelector::Name(name) => {
+                sheet_names.iter().find(|sheet| *sheet == name).or_else(|| {
+                    name.parse::<usize>()
+                        .ok()
+                        .and_then(|index| sheet_names.get(index))
+                })
+            }
+        };
+        found.map(String::as_str).ok_or_else(|| match self {
+            SheetSelector::First => eyre!("workbook has no sheets"),
+            SheetSelector::Name(name) => eyre!(
+                "sheet `{}` not found, the workbook has: {}",
+                name,
+                sheet_names.join(", ")
+            ),
+        })
+    }
+}
+
+/// A rectangular block of cells in A1 notation, such as `B2:F100`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CellRange {
+    /// 0-based row and column of the top-left cell.
+    start: (u32, u32),
+    /// 0-based row and column of the bottom-right cell.
+    end: (u32, u32),
+}
+
+impl CellRange {
+    pub(crate) fn parse(value: &str) -> Result<Self> {
+        let invalid = || {
+            eyre!(
+                "invalid `range` param: expected a range such as `A1:D20`, got `{}`",
+                value
+            )
+        };
+
+        let (start, end) = value.split_once(':').ok_or_else(invalid)?;
+        let start = parse_cell(start).ok_or_else(invalid)?;
+        let end = parse_cell(end).ok_or_else(invalid)?;
+        if start.0 > end.0 || start.1 > end.1 {
+            return Err(eyre!(
+                "invalid `range` param: `{}` ends before it starts",
+                value
+            ));
+        }
+        if end.0 >= MAX_ROWS || end.1 >= MAX_COLUMNS {
+            return Err(eyre!(
+                "invalid `range` param: `{}` goes beyond the {} rows and {} columns of a sheet",
+                value,
+                MAX_ROWS,
+                MAX_COLUMNS
+            ));
+        }
+
+        Ok(Self { start, end })
+    }
+
+    /// Cut the range out of a worksheet.
+    ///
+    /// Only the part inside the used area of the sheet is kept, so the empty rows and
+    /// columns around the data are never allocated.
+    pub(crate) fn select(&self, sheet: &Range<Data>) -> Range<Data> {
+        let (Some(used_start), Some(used_end)) = (sheet.start(), sheet.end()) else {
+            return Range::empty();
+        };
+        let start = (self.start.0.max(used_start.0), self.start.1.max(used_start.1));
+        let end = (self.end.0.min(used_end.0), self.end.1.min(used_end.1));
+        if start.0 > end.0 || start.1 > end.1 {
+            return Range::empty();
+        }
+        sheet.range(start, end)
+    }
+}
+
+/// Parse a cell reference such as `B2` or `$B$2` into a 0-based row and column.
+fn parse_cell(reference: &str) -> Option<(u32, u32)> {
+    let reference = reference.trim().replace('$', "");
+    let split = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = reference.split_at(split);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let column = letters.chars().try_fold(0u32, |column, letter| {
+        let digit = letter.to_ascii_uppercase() as u32 - 'A' as u32 + 1;
+        column.checked_mul(26)?.checked_add(digit)
+    })?;
+    let row = digits.parse::<u32>().ok().filter(|row| *row > 0)?;
+
+    Some((row - 1, column - 1))
+}
+
+/// A1 reference of a 0-based cell position.
+pub(crate) fn cell_name((row, column): (u32, u32)) -> String {
+    format!("{}{}", column_name(column), u64::from(row) + 1)
+}
+
+/// Column letters of a 0-based column, such as `AA` for 26.
+pub(crate) fn column_name(column: u32) -> String {
+    let mut letters = String::new();
+    let mut rest = column + 1;
+    while rest > 0 {
+        letters.insert(0, char::from(b'A' + ((rest - 1) % 26) as u8));
+        rest = (rest - 1) / 26;
+    }
+    letters
+}
+
+/// Convert a cell into a JSON value, keeping its type.
+///
+/// Formula cells hold the value cached by the application that saved the workbook.
+/// Fails for error cells such as `#DIV/0!`.
+pub(crate) fn cell_to_json(cell: &Data) -> std::result::Result<Value, CellErrorType> {
+    match cell {
+        Data::Empty => Ok(Value::Null),
+        Data::Bool(value) => Ok(Value::Bool(*value)),
+        Data::Int(value) => Ok(Value::from(*value)),
+        Data::Float(value) => Ok(float_to_json(*value)),
+        Data::String(value) => Ok(Value::String(value.clone())),
+        Data::DateTime(value) if value.is_duration() => {
+            Ok(value.as_duration().map_or(Value::Null, |duration| {
+                let seconds = duration.num_seconds();
+                Value::String(format!(
+                    "{}:{:02}:{:02}",
+                    seconds / 3600,
+                    seconds % 3600 / 60,
+                    seconds % 60
+                ))
+            }))
+        }
+        Data::DateTime(value) => Ok(value.as_datetime().map_or(Value::Null, |datetime| {
+            let text = if datetime.time() == NaiveTime::MIN {
+                datetime.format("%Y-%m-%d").to_string()
+            } else {
+                datetime.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+            };
+            Value::String(text)
+        })),
+        Data::DateTimeIso(value) | Data::DurationIso(value) => Ok(Value::String(value.clone())),
+        Data::Error(error) => Err(error.clone()),
+    }
+}
+
+/// Whole numbers become integers, since spreadsheets store every number as a double.
+fn float_to_json(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() <= MAX_SAFE_INTEGER {
+        return Value::from(value as i64);
+    }
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{ExcelDateTime, ExcelDateTimeType};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_range() {
+        let range = CellRange::parse("B2:$AA$100").unwrap();
+        assert_eq!(
+            range,
+            CellRange {
+                start: (1, 1),
+                end: (99, 26)
+            }
+        );
+
+        assert!(CellRange::parse("B2").is_err());
+        assert!(CellRange::parse("2B:C4").is_err());
+        assert!(CellRange::parse("A0:C4").is_err());
+        assert!(CellRange::parse("A1:XFD1048576").is_ok());
+        assert!(CellRange::parse("A1:XFE1").is_err());
+        assert_eq!(
+            CellRange::parse("A1:A1048577").unwrap_err().to_string(),
+            "invalid `range` param: `A1:A1048577` goes beyond the 1048576 rows and 16384 columns of a sheet"
+        );
+        assert_eq!(
+            CellRange::parse("C4:A1").unwrap_err().to_string(),
+            "invalid `range` param: `C4:A1` ends before it starts"
+        );
+    }
+
+    #[test]
+    fn test_select_within_used_area() {
+        let mut sheet = Range::new((2, 1), (3, 2));
+        sheet.set_value((2, 1), Data::String("Zone".to_string()));
+        sheet.set_value((3, 2), Data::Int(7));
+
+        let selected = CellRange::parse("A1:XFD1048576").unwrap().select(&sheet);
+        assert_eq!(selected.start(), Some((2, 1)));
+        assert_eq!(selected.end(), Some((3, 2)));
+
+        let outside = CellRange::parse("D1:E2").unwrap().select(&sheet);
+        assert!(outside.is_empty());
+        assert!(CellRange::parse("A1:B2").unwrap().select(&Range::empty()).is_empty());
+    }
+
+    #[test]
+    fn test_cell_name() {
+        assert_eq!(cell_name((0, 0)), "A1");
+        assert_eq!(cell_name((9, 25)), "Z10");
+        assert_eq!(cell_name((99, 26)), "AA100");
+    }
+
+    #[test]
+    fn test_resolve_sheet() {
+        let names = vec![
+            "Summary".to_string(),
+            "2024".to_string(),
+            "Expenses".to_string(),
+        ];
+
+        assert_eq!(SheetSelector::First.resolve(&names).unwrap(), "Summary");
+        assert_eq!(
+            SheetSelector::parse("Expenses")
+                .unwrap()
+                .resolve(&names)
+                .unwrap(),
+            "Expenses"
+        );
+        assert_eq!(
+            SheetSelector::parse("2").unwrap().resolve(&names).unwrap(),
+            "Expenses"
+        );
+        // A sheet name takes precedence over an index
+        assert_eq!(
+            SheetSelector::parse("2024")
+                .unwrap()
+                .resolve(&names)
+                .unwrap(),
+            "2024"
+        );
+        assert_eq!(
+            SheetSelector::parse("Budget")
+                .unwrap()
+                .resolve(&names)
+                .unwrap_err()
+                .to_string(),
+            "sheet `Budget` not found, the workbook has: Summary, 2024, Expenses"
+        );
+    }
+
+    #[test]
+    fn test_cell_to_json() {
+        let date = ExcelDateTime::new(45355.0, ExcelDateTimeType::DateTime, false);
+        let datetime = ExcelDateTime::new(45355.5625, ExcelDateTimeType::DateTime, false);
+        let duration = ExcelDateTime::new(1.5, ExcelDateTimeType::TimeDelta, false);
+
+        assert_eq!(cell_to_json(&Data::Float(1250.0)).unwrap(), json!(1250));
+        assert_eq!(cell_to_json(&Data::Float(-75.25)).unwrap(), json!(-75.25));
+        assert_eq!(cell_to_json(&Data::Float(1e300)).unwrap(), json!(1e300));
+        assert_eq!(cell_to_json(&Data::Bool(true)).unwrap(), json!(true));
+        assert_eq!(cell_to_json(&Data::Empty).unwrap(), Value::Null);
+        assert_eq!(
+            cell_to_json(&Data::DateTime(date)).unwrap(),
+            json!("2024-03-04")
+        );
+        assert_eq!(
+            cell_to_json(&Data::DateTime(datetime)).unwrap(),
+            json!("2024-03-04T13:30:00")
+        );
+        assert_eq!(
+            cell_to_json(&Data::DateTime(duration)).unwrap(),
+            json!("36:00:00")
+        );
+        assert_eq!(
+            cell_to_json(&Data::Error(CellErrorType::Div0)).unwrap_err(),
+            CellErrorType::Div0
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn xlsx_with_sheet_and_range() {
+    workbook_cmd("test-budget", "test-data/budget/output.json");
+}
+
+#[test]
+fn ods_first_sheet() {
+    workbook_cmd("test-inventory", "test-data/inventory/output.json");
+}
+
+fn remove_whitespace(s: &str) -> String {
+    s.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn workbook_cmd(arg: &str, output_file_path: &str) {
+    let expected = fs::read_to_string(output_file_path).unwrap();
+
+    // Execute the command
+    let output = Command::new("make")
+        .arg(arg)
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let result = String::from_utf8_lossy(&output.stdout);
+
+    println!("expected:\n{}", expected);
+    println!("result:\n{}", result);
+
+    assert_eq!(remove_whitespace(&expected), remove_whitespace(&result));
+}
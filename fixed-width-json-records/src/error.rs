@@ -0,0 +1,89 @@
+use csv_common::error::OnError;
+use fluvio_smartmodule::Result;
+use serde_json::{json, Value};
+
+/// A line that failed to convert, with its location in the input.
+#[derive(Debug)]
+pub(crate) struct LineError {
+    line: u64,
+    byte: u64,
+    message: String,
+}
+
+impl LineError {
+    /// Error for the 1-based `line` starting at `byte` of the input.
+    pub(crate) fn new(line: usize, byte: usize, err: impl std::fmt::Display) -> Self {
+        Self {
+            line: line as u64,
+            byte: byte as u64,
+            message: err.to_string(),
+        }
+    }
+
+    /// Apply the `on_error` policy to the error.
+    ///
+    /// Returns the error object to output in place of the line, or `None` to drop it.
+    pub(crate) fn handle(self, on_error: OnError, input: &[u8]) -> Result<Option<Value>> {
+        let Self {
+            line,
+            byte,
+            message,
+        } = self;
+        on_error.report(
+            message,
+            format_args!("line {}, byte {}", line, byte),
+            || {
+                json!({
+                    "line": line,
+                    "byte": byte,
+                    "raw": raw_line(input, byte),
+                })
+            },
+        )
+    }
+}
+
+/// The input line starting at `byte`.
+fn raw_line(input: &[u8], byte: u64) -> String {
+    let start = (byte as usize).min(input.len());
+    let line = input[start..]
+        .split(|b| *b == b'\n')
+        .next()
+        .unwrap_or_default();
+    String::from_utf8_lossy(line)
+        .trim_end_matches('\r')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies() {
+        let input = "00042ALICE\nXXXX7BOB\n";
+        let error = || LineError::new(2, 11, "field `id`: cannot convert `XXXX7` to integer");
+
+        let err = error().handle(OnError::Fail, input.as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, byte 11: field `id`: cannot convert `XXXX7` to integer"
+        );
+
+        assert_eq!(
+            error().handle(OnError::Skip, input.as_bytes()).unwrap(),
+            None
+        );
+
+        let emitted = error().handle(OnError::Emit, input.as_bytes()).unwrap();
+        assert_eq!(
+            emitted,
+            Some(json!({
+                "error": "field `id`: cannot convert `XXXX7` to integer",
+                "line": 2,
+                "byte": 11,
+                "raw": "XXXX7BOB",
+            }))
+        );
+    }
+}
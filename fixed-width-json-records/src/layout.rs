@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use csv_common::schema::ColumnType;
+use csv_common::typing;
+use fluvio_smartmodule::{eyre, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Positions and conversion of the fields of a fixed-width line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Layout {
+    fields: Vec<FieldSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FieldSpec {
+    /// Field name, before `header_case`.
+    name: String,
+    /// Position of the first character, counting from 1.
+    start: usize,
+    /// Width of the field in characters.
+    length: usize,
+    /// Target type; when omitted the field follows the `infer_types` setting.
+    #[serde(rename = "type", default)]
+    field_type: Option<ColumnType>,
+    /// Remove the padding around the value.
+    #[serde(default = "default_trim")]
+    trim: bool,
+    /// Side the value is aligned to; the padding is on the other side.
+    #[serde(default)]
+    justification: Justification,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Justification {
+    #[default]
+    Left,
+    Right,
+}
+
+fn default_trim() -> bool {
+    true
+}
+
+impl Layout {
+    /// Parse and validate the JSON `layout` param.
+    pub(crate) fn parse(raw: &str) -> Result<Self> {
+        let layout: Layout = serde_json::from_str(raw)
+            .map_err(|err| eyre!("cannot parse `layout` param: {}", err))?;
+
+        if layout.fields.is_empty() {
+            return Err(eyre!("`layout` param must list at least one field"));
+        }
+        let mut seen = HashSet::new();
+        for field in &layout.fields {
+            if field.name.is_empty() {
+                return Err(eyre!("layout has a field with an empty name"));
+            }
+            if !seen.insert(field.name.as_str()) {
+                return Err(eyre!("layout lists field `{}` more than once", field.name));
+            }
+            if field.start == 0 {
+                return Err(eyre!(
+                    "layout field `{}` must start at 1 or later",
+                    field.name
+                ));
+            }
+            if field.length == 0 {
+                return Err(eyre!(
+                    "layout field `{}` must have a positive length",
+                    field.name
+                ));
+            }
+            if field.start.checked_add(field.length).is_none() {
+                return Err(eyre!("layout field `{}` ends out of range", field.name));
+            }
+        }
+
+        Ok(layout)
+    }
+
+    pub(crate) fn fields(&self) -> &[FieldSpec] {
+        &self.fields
+    }
+}
+
+impl FieldSpec {
+    /// Field name as listed in the layout.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Cut the field out of a line, removing the padding when `trim` is set.
+    ///
+    /// Characters past the end of a short line are treated as padding.
+    pub(crate) fn extract<'a>(&self, line: &'a str) -> &'a str {
+        let offset = |chars: usize| {
+            line.char_indices()
+                .nth(chars)
+                .map_or(line.len(), |(i, _)| i)
+        };
+        let start = self.start - 1;
+        let cell = &line[offset(start)..offset(start.saturating_add(self.length))];
+
+        match (self.trim, self.justification) {
+            (false, _) => cell,
+            (true, Justification::Left) => cell.trim_end(),
+            (true, Justification::Right) => cell.trim_start(),
+        }
+    }
+
+    /// Convert an extracted cell according to the spec.
+    ///
+    /// An empty cell becomes `null` for the non-string types, so `string` fields keep `""`.
+    pub(crate) fn convert(&self, cell: &str, infer_types: bool) -> Result<Value> {
+        match self.field_type {
+            Some(ColumnType::String) => Ok(Value::String(cell.to_string())),
+            Some(_) if cell.is_empty() => Ok(Value::Null),
+            Some(field_type) => field_type.cast(cell).ok_or_else(|| {
+                eyre!(
+                    "field `{}`: cannot convert `{}` to {}",
+                    self.name,
+                    cell,
+                    field_type.name()
+                )
+            }),
+            None => Ok(typing::cell_to_json(cell, infer_types)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_with_justification() {
+        let raw = r#"[
+            {"name": "id", "start": 1, "length": 5, "justification": "right"},
+            {"name": "name", "start": 6, "length": 8},
+            {"name": "code", "start": 14, "length": 4, "trim": false}
+        ]"#;
+        let layout = Layout::parse(raw).unwrap();
+        let [id, name, code] = layout.fields() else {
+            panic!("expected three fields");
+        };
+
+        let line = "   42Zoë     AB";
+
+        assert_eq!(id.extract(line), "42");
+        assert_eq!(name.extract(line), "Zoë");
+        // Short line: the missing characters are padding
+        assert_eq!(code.extract(line), "AB");
+        assert_eq!(code.extract("short"), "");
+    }
+
+    #[test]
+    fn test_convert_types() {
+        let raw = r#"[
+            {"name": "id", "start": 1, "length": 5, "type": "integer"},
+            {"name": "active", "start": 6, "length": 1, "type": "boolean"},
+            {"name": "zip", "start": 7, "length": 5},
+            {"name": "code", "start": 12, "length": 2, "type": "string"}
+        ]"#;
+        let layout = Layout::parse(raw).unwrap();
+        let [id, active, zip, code] = layout.fields() else {
+            panic!("expected four fields");
+        };
+
+        assert_eq!(id.convert("00042", false).unwrap(), json!(42));
+        assert_eq!(id.convert("", false).unwrap(), Value::Null);
+        assert_eq!(active.convert("1", false).unwrap(), json!(true));
+        assert!(active.convert("Y", false).is_err());
+        assert_eq!(zip.convert("02134", true).unwrap(), json!("02134"));
+        assert_eq!(zip.convert("", false).unwrap(), json!(""));
+        assert_eq!(code.convert("", true).unwrap(), json!(""));
+
+        assert_eq!(
+            id.convert("4.2", false).unwrap_err().to_string(),
+            "field `id`: cannot convert `4.2` to integer"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_layouts() {
+        let err = Layout::parse(r#"[{"name": "id", "start": 0, "length": 5}]"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "layout field `id` must start at 1 or later"
+        );
+
+        let err = Layout::parse(r#"[{"name": "id", "start": 18446744073709551615, "length": 1}]"#)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "layout field `id` ends out of range");
+
+        assert!(Layout::parse("[]").is_err());
+        assert!(Layout::parse(r#"[{"name": "id", "start": 1, "length": 0}]"#).is_err());
+        assert!(Layout::parse(r#"[{"name": "id", "start": 1}]"#).is_err());
+        assert!(Layout::parse(r#"[{"name": "id", "start": 1, "length": 2, "pad": "0"}]"#).is_err());
+        assert!(Layout::parse(
+            r#"[{"name": "id", "start": 1, "length": 2}, {"name": "id", "start": 3, "length": 2}]"#
+        )
+        .is_err());
+    }
+}
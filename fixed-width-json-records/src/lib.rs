@@ -0,0 +1,284 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use csv_common::case::HeaderCase;
+use csv_common::error::OnError;
+use csv_common::params::parse_bool;
+use fluvio_smartmodule::{
+    dataplane::smartmodule::SmartModuleExtraParams, eyre, smartmodule, RecordData, Result,
+    SmartModuleRecord,
+};
+use serde_json::{Map, Value};
+
+mod error;
+mod layout;
+
+use error::LineError;
+use layout::Layout;
+
+static PARAMS: OnceLock<Params> = OnceLock::new();
+const LAYOUT_PARAM_NAME: &str = "layout";
+const HEADER_CASE_PARAM_NAME: &str = "header_case";
+const INFER_TYPES_PARAM_NAME: &str = "infer_types";
+const ON_ERROR_PARAM_NAME: &str = "on_error";
+
+#[smartmodule(array_map)]
+pub fn array_map(record: &SmartModuleRecord) -> Result<Vec<(Option<RecordData>, RecordData)>> {
+    let params = PARAMS.get().expect("params is not initialized");
+
+    process_fixed_width_records(record, params)
+}
+
+/// Process a fixed-width record and convert each line into an individual JSON record.
+fn process_fixed_width_records(
+    record: &SmartModuleRecord,
+    params: &Params,
+) -> Result<Vec<(Option<RecordData>, RecordData)>> {
+    let input = record.value.as_ref();
+
+    let mut rows: Vec<(Option<RecordData>, RecordData)> = Vec::new();
+
+    let mut line_start = 0;
+    for (index, line) in input.split(|b| *b == b'\n').enumerate() {
+        let byte = line_start;
+        line_start += line.len() + 1;
+
+        // Blank lines, including the one after a trailing newline, produce no output
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+
+        let json_object = match line_to_json(line, params) {
+            Ok(json_object) => json_object,
+            Err(err) => match LineError::new(index + 1, byte, err).handle(params.on_error, input)? {
+                Some(error_object) => error_object,
+                None => continue,
+            },
+        };
+        rows.push((None, RecordData::from(serde_json::to_vec(&json_object)?)));
+    }
+
+    Ok(rows)
+}
+
+/// Cut a line into the layout fields and convert them into a JSON object.
+fn line_to_json(line: &[u8], params: &Params) -> Result<Value> {
+    let line = std::str::from_utf8(line).map_err(|err| eyre!("invalid UTF-8: {}", err))?;
+
+    let mut json_object = Map::new();
+    for (field, name) in params.layout.fields().iter().zip(&params.names) {
+        let value = field.convert(field.extract(line), params.infer_types)?;
+        json_object.insert(name.clone(), value);
+    }
+
+    Ok(Value::Object(json_object))
+}
+
+#[smartmodule(init)]
+fn init(params: SmartModuleExtraParams) -> Result<()> {
+    let layout_param = match params.get(LAYOUT_PARAM_NAME) {
+        Some(v) => Layout::parse(v)?,
+        None => return Err(eyre!("`layout` param is required")),
+    };
+
+    let case_param = match params.get(HEADER_CASE_PARAM_NAME) {
+        Some(v) => HeaderCase::try_from(v.to_string())
+            .map_err(|e| eyre!("failed to parse header_case: {}", e))?,
+        None => HeaderCase::None,
+    };
+
+    let infer_types_param = match params.get(INFER_TYPES_PARAM_NAME) {
+        Some(v) => parse_bool(INFER_TYPES_PARAM_NAME, v)?,
+        None => false,
+    };
+
+    let on_error_param = match params.get(ON_ERROR_PARAM_NAME) {
+        Some(v) => OnError::try_from(v.to_string())
+            .map_err(|e| eyre!("failed to parse on_error: {}", e))?,
+        None => OnError::Fail,
+    };
+
+    PARAMS
+        .set(Params::new(
+            layout_param,
+            case_param,
+            infer_types_param,
+            on_error_param,
+        )?)
+        .expect("params is already initialized");
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Params {
+    layout: Layout,
+    /// Output name of each layout field, after the header case.
+    names: Vec<String>,
+    infer_types: bool,
+    on_error: OnError,
+}
+
+impl Params {
+    fn new(
+        layout: Layout,
+        header_case: HeaderCase,
+        infer_types: bool,
+        on_error: OnError,
+    ) -> Result<Self> {
+        let names: Vec<String> = layout
+            .fields()
+            .iter()
+            .map(|field| header_case.apply(field.name()))
+            .collect();
+
+        let mut seen = HashSet::new();
+        if let Some(name) = names.iter().find(|name| !seen.insert(name.as_str())) {
+            return Err(eyre!(
+                "`header_case` maps several layout fields to `{}`",
+                name
+            ));
+        }
+
+        Ok(Self {
+            layout,
+            names,
+            infer_types,
+            on_error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluvio_smartmodule::Record;
+    use serde_json::json;
+
+    const ACCOUNTS_LAYOUT: &str = r#"[
+        {"name": "Account Id", "start": 1, "length": 6, "type": "integer", "justification": "right"},
+        {"name": "Holder Name", "start": 7, "length": 12},
+        {"name": "Balance", "start": 19, "length": 10, "justification": "right"},
+        {"name": "Active", "start": 29, "length": 1, "type": "boolean"}
+    ]"#;
+
+    fn params(header_case: HeaderCase, infer_types: bool, on_error: OnError) -> Params {
+        Params::new(
+            Layout::parse(ACCOUNTS_LAYOUT).unwrap(),
+            header_case,
+            infer_types,
+            on_error,
+        )
+        .unwrap()
+    }
+
+    // Utility function to convert the output records into a JSON byte vector
+    fn convert_records_to_json(result: Vec<(Option<RecordData>, RecordData)>) -> Vec<u8> {
+        let result_json: Vec<Value> = result
+            .into_iter()
+            .map(|(_, r)| serde_json::from_slice::<Value>(r.as_ref()).unwrap())
+            .collect();
+
+        serde_json::to_vec(&result_json).unwrap()
+    }
+
+    #[test]
+    fn test_default_conversion() {
+        let data = "000042Alice Martin   1250.501\r\n000043Bob             -75.000\r\n\r\n";
+        let record = SmartModuleRecord::new(Record::new(data), 0, 0);
+        let params = params(HeaderCase::Snake, false, OnError::Fail);
+
+        // Test Conversion
+        let computed = process_fixed_width_records(&record, &params).unwrap();
+        assert!(computed.iter().all(|(key, _)| key.is_none()));
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"account_id": 42, "holder_name": "Alice Martin", "balance": "1250.50", "active": true}),
+            json!({"account_id": 43, "holder_name": "Bob", "balance": "-75.00", "active": false}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_infer_types_and_camel_case() {
+        let data = "000042Alice Martin   1250.501\n000044Carol\n";
+        let record = SmartModuleRecord::new(Record::new(data), 0, 0);
+        let params = params(HeaderCase::Camel, true, OnError::Fail);
+
+        // Test Conversion
+        let computed = process_fixed_width_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"accountId": 42, "holderName": "Alice Martin", "balance": 1250.5, "active": true}),
+            json!({"accountId": 44, "holderName": "Carol", "balance": null, "active": null}),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_on_error_emit() {
+        let data = "000042Alice Martin   1250.501\nXXXXXXBob             -75.000\n";
+        let record = SmartModuleRecord::new(Record::new(data), 0, 0);
+        let params = params(HeaderCase::None, false, OnError::Emit);
+
+        // Test Conversion
+        let computed = process_fixed_width_records(&record, &params).unwrap();
+        let computed_output = convert_records_to_json(computed);
+
+        // Prepare Result
+        let expected_json = vec![
+            json!({"Account Id": 42, "Holder Name": "Alice Martin", "Balance": "1250.50", "Active": true}),
+            json!({
+                "error": "field `Account Id`: cannot convert `XXXXXX` to integer",
+                "line": 2,
+                "byte": 30,
+                "raw": "XXXXXXBob             -75.000",
+            }),
+        ];
+        let expected_output = serde_json::to_vec(&expected_json).unwrap();
+
+        assert_eq!(computed_output, expected_output);
+    }
+
+    #[test]
+    fn test_on_error_fail_and_skip() {
+        let data = "000042Alice Martin   1250.501\n000043Bob             -75.00?\n";
+        let record = SmartModuleRecord::new(Record::new(data), 0, 0);
+
+        let err =
+            process_fixed_width_records(&record, &params(HeaderCase::None, false, OnError::Fail))
+                .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, byte 30: field `Active`: cannot convert `?` to boolean"
+        );
+
+        let computed =
+            process_fixed_width_records(&record, &params(HeaderCase::None, false, OnError::Skip))
+                .unwrap();
+        assert_eq!(computed.len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_colliding_names() {
+        let layout = Layout::parse(
+            r#"[{"name": "Zone Id", "start": 1, "length": 2}, {"name": "zone-id", "start": 3, "length": 2}]"#,
+        )
+        .unwrap();
+
+        let err = Params::new(layout, HeaderCase::Snake, false, OnError::Fail).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`header_case` maps several layout fields to `zone_id`"
+        );
+    }
+}
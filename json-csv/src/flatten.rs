@@ -0,0 +1,116 @@
+use serde_json::{Map, Value};
+
+use crate::{Nested, Params};
+
+/// Flatten a JSON object into `(column, cell)` pairs, in field order.
+///
+/// With `nested=flatten`, objects produce `parent.child` columns and arrays produce
+/// `parent[0]` columns, the header syntax read back by `unflatten` in the CSV modules.
+/// Empty objects and arrays, and all nested values with `nested=json`, are written as
+/// JSON text.
+pub(crate) fn flatten_object(object: &Map<String, Value>, params: &Params) -> Vec<(String, String)> {
+    let mut cells = Vec::new();
+    for (key, value) in object {
+        flatten_value(key.clone(), value, params, &mut cells);
+    }
+    cells
+}
+
+fn flatten_value(path: String, value: &Value, params: &Params, cells: &mut Vec<(String, String)>) {
+    match (value, &params.nested) {
+        (Value::Object(fields), Nested::Flatten) if !fields.is_empty() => {
+            for (key, child) in fields {
+                let child_path = format!("{}{}{}", path, params.path_separator, key);
+                flatten_value(child_path, child, params, cells);
+            }
+        }
+        (Value::Array(items), Nested::Flatten) if !items.is_empty() => {
+            for (index, child) in items.iter().enumerate() {
+                flatten_value(format!("{}[{}]", path, index), child, params, cells);
+            }
+        }
+        _ => cells.push((path, cell_text(value))),
+    }
+}
+
+/// Text of a single cell; `null` is an empty cell.
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn flatten(value: Value, params: &Params) -> Vec<(String, String)> {
+        match value {
+            Value::Object(object) => flatten_object(&object, params),
+            _ => panic!("expected an object"),
+        }
+    }
+
+    fn pairs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(column, cell)| (column.to_string(), cell.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_flatten_nested_values() {
+        let value = json!({
+            "id": 7,
+            "address": {"city": "Paris", "geo": {"lat": 48.85}},
+            "tags": ["new", null],
+            "active": true,
+            "notes": [],
+        });
+
+        let cells = flatten(value, &Params::default());
+
+        assert_eq!(
+            cells,
+            pairs(&[
+                ("id", "7"),
+                ("address.city", "Paris"),
+                ("address.geo.lat", "48.85"),
+                ("tags[0]", "new"),
+                ("tags[1]", ""),
+                ("active", "true"),
+                ("notes", "[]"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_flatten_with_separator() {
+        let params = Params {
+            path_separator: "/".to_string(),
+            ..Params::default()
+        };
+
+        let cells = flatten(json!({"a": {"b": {"c": "x"}}}), &params);
+
+        assert_eq!(cells, pairs(&[("a/b/c", "x")]));
+    }
+
+    #[test]
+    fn test_nested_as_json() {
+        let params = Params {
+            nested: Nested::Json,
+            ..Params::default()
+        };
+
+        let cells = flatten(json!({"id": "1", "address": {"city": "Paris"}, "tags": [1, 2]}), &params);
+
+        assert_eq!(
+            cells,
+            pairs(&[("id", "1"), ("address", r#"{"city":"Paris"}"#), ("tags", "[1,2]")])
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use fluvio_smartmodule::{
+    dataplane::smartmodule::SmartModuleExtraParams, eyre, smartmodule, RecordData, Result,
+    SmartModuleRecord,
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use csv_common::dialect::Dialect;
+use csv_common::params::{parse_bool, parse_list, DEFAULT_PATH_SEPARATOR};
+
+mod flatten;
+
+use flatten::flatten_object;
+
+static PARAMS: OnceLock<Params> = OnceLock::new();
+const DELIMITER_PARAM_NAME: &str = "delimiter";
+const DIALECT_PARAM_NAME: &str = "dialect";
+const COLUMNS_PARAM_NAME: &str = "columns";
+const HEADER_PARAM_NAME: &str = "header";
+const NESTED_PARAM_NAME: &str = "nested";
+const PATH_SEPARATOR_PARAM_NAME: &str = "path_separator";
+
+#[smartmodule(map)]
+pub fn map(record: &SmartModuleRecord) -> Result<(Option<RecordData>, RecordData)> {
+    let params = PARAMS.get().expect("params is not initialized");
+
+    let key = record.key.clone();
+    let value = process_json_record(record, params)?;
+
+    Ok((key, RecordData::from(value)))
+}
+
+/// Convert a JSON object, or an array of objects, into CSV text with a header row.
+fn process_json_record(record: &SmartModuleRecord, params: &Params) -> Result<Vec<u8>> {
+    let objects = match serde_json::from_slice(record.value.as_ref())? {
+        Value::Object(object) => vec![object],
+        Value::Array(items) => items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| match item {
+                Value::Object(object) => Ok(object),
+                other => Err(eyre!(
+                    "item {}: expected a JSON object, found {}",
+                    index,
+                    json_type(&other)
+                )),
+            })
+            .collect::<Result<Vec<Map<String, Value>>>>()?,
+        other => {
+            return Err(eyre!(
+                "expected a JSON object or an array of objects, found {}",
+                json_type(&other)
+            ))
+        }
+    };
+
+    let rows: Vec<Vec<(String, String)>> = objects
+        .iter()
+        .map(|object| flatten_object(object, params))
+        .collect();
+
+    // Columns come from the params, or from the fields in the order they first appear
+    let columns = match &params.columns {
+        Some(columns) => columns.clone(),
+        None => discover_columns(&rows),
+    };
+    if columns.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut csv_writer = params.dialect.writer_builder().from_writer(Vec::new());
+
+    if params.header {
+        write_fields(&mut csv_writer, columns.iter().map(String::as_str), params)?;
+    }
+
+    for row in &rows {
+        let cells: HashMap<&str, &str> = row
+            .iter()
+            .map(|(column, cell)| (column.as_str(), cell.as_str()))
+            .collect();
+        write_fields(
+            &mut csv_writer,
+            columns
+                .iter()
+                .map(|column| cells.get(column.as_str()).copied().unwrap_or_default()),
+            params,
+        )?;
+    }
+
+    let output = csv_writer
+        .into_inner()
+        .map_err(|err| eyre!("cannot write csv: {}", err.error()))?;
+    Ok(params.dialect.restore_output(output))
+}
+
+/// Write one CSV record, substituting a multi-byte delimiter in the fields.
+fn write_fields<'a>(
+    csv_writer: &mut csv::Writer<Vec<u8>>,
+    fields: impl Iterator<Item = &'a str>,
+    params: &Params,
+) -> Result<()> {
+    let fields = fields
+        .map(|field| params.dialect.prepare_field(field))
+        .collect::<Result<Vec<_>>>()?;
+    csv_writer.write_record(fields.iter().map(|field| field.as_bytes()))?;
+    Ok(())
+}
+
+/// Collect the column names of all rows, in the order they first appear.
+fn discover_columns(rows: &[Vec<(String, String)>]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for (column, _) in rows.iter().flatten() {
+        if !columns.contains(column) {
+            columns.push(column.clone());
+        }
+    }
+    columns
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[smartmodule(init)]
+fn init(params: SmartModuleExtraParams) -> Result<()> {
+    let dialect_param = Dialect::from_params(
+        params.get(DIALECT_PARAM_NAME).map(String::as_str),
+        params.get(DELIMITER_PARAM_NAME).map(String::as_str),
+    )?;
+    if dialect_param.auto_delimiter {
+        return Err(eyre!("`delimiter=auto` can only be used to read CSV"));
+    }
+
+    let columns_param = params
+        .get(COLUMNS_PARAM_NAME)
+        .map(|v| parse_list(COLUMNS_PARAM_NAME, v))
+        .transpose()?;
+
+    let header_param = match params.get(HEADER_PARAM_NAME) {
+        Some(v) => parse_bool(HEADER_PARAM_NAME, v)?,
+        None => true,
+    };
+
+    let nested_param = match params.get(NESTED_PARAM_NAME) {
+        Some(v) => Nested::try_from(v.to_string())
+            .map_err(|e| eyre!("failed to parse nested: {}", e))?,
+        None => Nested::Flatten,
+    };
+
+    let path_separator_param = match params.get(PATH_SEPARATOR_PARAM_NAME) {
+        Some(v) if v.is_empty() => return Err(eyre!("`path_separator` param cannot be empty")),
+        Some(v) => v.to_string(),
+        None => DEFAULT_PATH_SEPARATOR.to_string(),
+    };
+
+    PARAMS
+        .set(Params {
+            dialect: dialect_param,
+            columns: columns_param,
+            header: header_param,
+            nested: nested_param,
+            path_separator: path_separator_param,
+        })
+        .expect("params is already initialized");
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Params {
+    dialect: Dialect,
+    columns: Option<Vec<String>>,
+    header: bool,
+    nested: Nested,
+    path_separator: String,
+}
+
+/// How nested objects and arrays are written.
+#[derive(Debug, Default)]
+enum Nested {
+    /// One column per leaf value, named by its path.
+    #[default]
+    Flatten,
+    /// One column per top-level field, holding the JSON text.
+    Json,
+}
+
+impl TryFrom<String> for Nested {
+    type Error = &'static str;
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "" | "flatten" => Ok(Nested::Flatten),
+            "json" => Ok(Nested::Json),
+            _ => Err("Invalid nested mode"),
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::default(),
+            columns: None,
+            header: true,
+            nested: Nested::Flatten,
+            path_separator: DEFAULT_PATH_SEPARATOR.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluvio_smartmodule::Record;
+    use serde_json::json;
+
+    fn convert(value: Value, params: &Params) -> String {
+        let record = SmartModuleRecord::new(Record::new(value.to_string()), 0, 0);
+        let output = process_json_record(&record, params).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_single_object() {
+        let value = json!({"name": "Alice", "age": 30, "member": true, "email": null});
+
+        let csv = convert(value, &Params::default());
+
+        assert_eq!(csv, "name,age,member,email\nAlice,30,true,\n");
+    }
+
+    #[test]
+    fn test_array_with_missing_fields() {
+        let value = json!([
+            {"id": 1, "city": "Paris"},
+            {"id": 2, "zip": "75001"},
+            {"city": "Lyon, FR", "id": 3},
+        ]);
+
+        let csv = convert(value, &Params::default());
+
+        assert_eq!(csv, "id,city,zip\n1,Paris,\n2,,75001\n3,\"Lyon, FR\",\n");
+    }
+
+    #[test]
+    fn test_explicit_columns_without_header() {
+        let value = json!([{"a": 1, "b": 2, "c": 3}, {"c": 6, "a": 4}]);
+        let params = Params {
+            columns: Some(vec!["c".to_string(), "a".to_string(), "d".to_string()]),
+            header: false,
+            dialect: Dialect::from_params(None, Some(";")).unwrap(),
+            ..Params::default()
+        };
+
+        let csv = convert(value, &params);
+
+        assert_eq!(csv, "3;1;\n6;4;\n");
+    }
+
+    #[test]
+    fn test_nested_values() {
+        let value = json!([{"id": "1", "address": {"city": "Paris"}, "tags": ["new", "vip"]}]);
+
+        let flattened = convert(value.clone(), &Params::default());
+        assert_eq!(flattened, "id,address.city,tags[0],tags[1]\n1,Paris,new,vip\n");
+
+        let params = Params {
+            nested: Nested::Json,
+            ..Params::default()
+        };
+        let encoded = convert(value, &params);
+        assert_eq!(
+            encoded,
+            "id,address,tags\n1,\"{\"\"city\"\":\"\"Paris\"\"}\",\"[\"\"new\"\",\"\"vip\"\"]\"\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_array() {
+        assert_eq!(convert(json!([]), &Params::default()), "");
+
+        let params = Params {
+            columns: Some(vec!["a".to_string(), "b".to_string()]),
+            ..Params::default()
+        };
+        assert_eq!(convert(json!([]), &params), "a,b\n");
+    }
+
+    #[test]
+    fn test_rejects_non_objects() {
+        let record = SmartModuleRecord::new(Record::new("[{\"a\": 1}, 2]"), 0, 0);
+        let err = process_json_record(&record, &Params::default()).unwrap_err();
+        assert_eq!(err.to_string(), "item 1: expected a JSON object, found a number");
+
+        let record = SmartModuleRecord::new(Record::new("\"text\""), 0, 0);
+        let err = process_json_record(&record, &Params::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected a JSON object or an array of objects, found a string"
+        );
+    }
+
+    /// Converts the csv-json-array output of a fixture back to CSV with the fixture's
+    /// dialect and checks that every data row matches the original `input.csv`.
+    fn assert_round_trip(
+        input_csv: &str,
+        output_json: &str,
+        dialect: &str,
+        has_headers: bool,
+        columns: &[&str],
+    ) {
+        let dialect = Dialect::from_params(Some(dialect), None).unwrap();
+        let mut reader = dialect
+            .reader_builder()
+            .has_headers(has_headers)
+            .from_reader(input_csv.as_bytes());
+        let original: Vec<csv::StringRecord> = reader.records().map(|row| row.unwrap()).collect();
+
+        let params = Params {
+            dialect,
+            columns: Some(columns.iter().map(|column| column.to_string()).collect()),
+            ..Params::default()
+        };
+        let value: Value = serde_json::from_str(output_json).unwrap();
+        let csv = convert(value, &params);
+
+        let mut reader = params
+            .dialect
+            .reader_builder()
+            .has_headers(false)
+            .from_reader(csv.as_bytes());
+        let written: Vec<csv::StringRecord> = reader.records().map(|row| row.unwrap()).collect();
+
+        assert_eq!(written[0], csv::StringRecord::from(columns.to_vec()));
+        assert_eq!(written.len() - 1, original.len());
+        for (written_row, original_row) in written[1..].iter().zip(&original) {
+            assert_eq!(written_row, original_row);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_fixtures() {
+        assert_round_trip(
+            include_str!("../../csv-json-array/test-data/comma/input.csv"),
+            include_str!("../../csv-json-array/test-data/comma/output.json"),
+            "{}",
+            true,
+            &[
+                "Timestamp", "Labels", "I_w_BLO_Weg", "O_w_BLO_power", "O_w_BLO_voltage",
+                "I_w_BHL_Weg", "O_w_BHL_power", "O_w_BHL_voltage", "I_w_BHR_Weg",
+                "O_w_BHR_power", "O_w_BHR_voltage", "I_w_BRU_Weg", "O_w_BRU_power",
+                "O_w_BRU_voltage", "I_w_HR_Weg", "O_w_HR_power", "O_w_HR_voltage",
+                "I_w_HL_Weg", "O_w_HL_power", "O_w_HL_voltage",
+            ],
+        );
+        assert_round_trip(
+            include_str!("../../csv-json-array/test-data/transit/input.csv"),
+            include_str!("../../csv-json-array/test-data/transit/output.json"),
+            "{}",
+            true,
+            &["location_id", "borough", "zone", "service_zone"],
+        );
+        assert_round_trip(
+            include_str!("../../csv-json-array/test-data/semicolon-snake/input.csv"),
+            include_str!("../../csv-json-array/test-data/semicolon-snake/output.json"),
+            r#"{"delimiter": ";"}"#,
+            true,
+            &["username", "identifier", "first_name", "last_name"],
+        );
+        assert_round_trip(
+            include_str!("../../csv-json-array/test-data/semicolon-camel/input.csv"),
+            include_str!("../../csv-json-array/test-data/semicolon-camel/output.json"),
+            r#"{"delimiter": ";"}"#,
+            true,
+            &["username", "identifier", "firstName", "lastName"],
+        );
+        assert_round_trip(
+            include_str!("../../csv-json-array/test-data/headerless/input.csv"),
+            include_str!("../../csv-json-array/test-data/headerless/output.json"),
+            r#"{"delimiter": ";"}"#,
+            false,
+            &["timestamp", "device", "state", "temperature"],
+        );
+        assert_round_trip(
+            include_str!("../../csv-json-array/test-data/typed/input.csv"),
+            include_str!("../../csv-json-array/test-data/typed/output.json"),
+            "{}",
+            true,
+            &["store_id", "zip_code", "city", "revenue", "open", "manager"],
+        );
+        assert_round_trip(
+            include_str!("../../csv-json-array/test-data/tab/input.csv"),
+            include_str!("../../csv-json-array/test-data/tab/output.json"),
+            r##"{"delimiter": "\\t", "comment": "#"}"##,
+            true,
+            &["id", "description", "amount"],
+        );
+    }
+}
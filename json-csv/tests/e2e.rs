@@ -0,0 +1,41 @@
+use std::{fs, process::Command};
+
+#[test]
+fn json_with_nested_values() {
+    json_cmd("test-nested", "test-data/nested/output.csv");
+}
+
+#[test]
+fn json_with_semicolon_and_json_encoding() {
+    json_cmd(
+        "test-semicolon-json",
+        "test-data/semicolon-json/output.csv",
+    );
+}
+
+#[test]
+fn json_with_selected_columns() {
+    json_cmd(
+        "test-columns",
+        "test-data/columns/output.csv",
+    );
+}
+
+fn json_cmd(arg: &str, output_file_path: &str) {
+    // Read the expected output from a file
+    let expected_output = fs::read_to_string(output_file_path).expect("file not found");
+
+    // Execute the command
+    let output = Command::new("make")
+        .arg(arg)
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout_str = String::from_utf8_lossy(&output.stdout);
+
+    println!("expected:\n{}", expected_output);
+    println!("result:\n{}", stdout_str);
+
+    assert_eq!(stdout_str.trim_end(), expected_output.trim_end());
+}
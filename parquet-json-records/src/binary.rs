@@ -0,0 +1,215 @@
+use std::borrow::Cow;
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use fluvio_smartmodule::{eyre, Result};
+use parquet::basic::LogicalType;
+use parquet::schema::types::Type;
+use serde_json::Value;
+
+/// How binary values are written.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) enum BytesEncoding {
+    /// Lowercase hexadecimal strings.
+    #[default]
+    Hex,
+    /// Standard base64 strings, with padding.
+    Base64,
+    /// URL-safe base64 strings, without padding.
+    Base64Url,
+    /// UTF-8 strings when the bytes are valid UTF-8, standard base64 strings otherwise.
+    Utf8,
+}
+
+impl TryFrom<String> for BytesEncoding {
+    type Error = &'static str;
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "" | "hex" => Ok(BytesEncoding::Hex),
+            "base64" => Ok(BytesEncoding::Base64),
+            "base64url" => Ok(BytesEncoding::Base64Url),
+            "utf8" => Ok(BytesEncoding::Utf8),
+            _ => Err("Invalid bytes encoding"),
+        }
+    }
+}
+
+impl BytesEncoding {
+    /// Convert binary data into a JSON string.
+    pub(crate) fn to_json(self, data: &[u8]) -> Value {
+        let text = match self {
+            BytesEncoding::Hex => hex::encode(data),
+            BytesEncoding::Base64 => STANDARD.encode(data),
+            BytesEncoding::Base64Url => URL_SAFE_NO_PAD.encode(data),
+            BytesEncoding::Utf8 => match std::str::from_utf8(data) {
+                Ok(text) => text.to_string(),
+                Err(_) => STANDARD.encode(data),
+            },
+        };
+        Value::String(text)
+    }
+}
+
+/// Encoding of the binary values of a field, with the overrides of its nested fields.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct BytesEncodings {
+    encoding: BytesEncoding,
+    fields: Vec<(String, BytesEncodings)>,
+}
+
+impl BytesEncodings {
+    /// Parse the `bytes_encoding` param: a comma-separated list of a default encoding and
+    /// of `column=encoding` overrides, where columns are top-level names or dotted paths.
+    pub(crate) fn parse(value: &str) -> Result<Self> {
+        let mut encodings = Self::default();
+        let mut overrides = Vec::new();
+        let mut has_default = false;
+        for item in value.split(',').map(str::trim) {
+            match item.split_once('=') {
+                Some((column, encoding)) => overrides.push((column.trim(), encoding.trim())),
+                None if has_default => {
+                    return Err(eyre!(
+                        "invalid `bytes_encoding` param: more than one default encoding in `{}`",
+                        value
+                    ))
+                }
+                None => {
+                    encodings.encoding = parse_encoding(item)?;
+                    has_default = true;
+                }
+            }
+        }
+
+        // Enclosing fields first, so that nested fields inherit their encoding
+        overrides.sort_by_key(|(column, _)| column.split('.').count());
+        for (column, encoding) in overrides {
+            let path: Vec<&str> = column.split('.').collect();
+            if path.iter().any(|name| name.is_empty()) {
+                return Err(eyre!("invalid `bytes_encoding` param: empty name in `{}`", value));
+            }
+            let encoding = parse_encoding(encoding)?;
+            path.iter()
+                .fold(&mut encodings, |group, name| group.field_mut(name))
+                .encoding = encoding;
+        }
+
+        Ok(encodings)
+    }
+
+    /// Encodings of a field of this group; fields without overrides inherit the encoding
+    /// of the group.
+    pub(crate) fn field(&self, name: &str) -> Cow<'_, BytesEncodings> {
+        match self.fields.iter().find(|(field, _)| field == name) {
+            Some((_, encodings)) => Cow::Borrowed(encodings),
+            None => Cow::Owned(BytesEncodings {
+                encoding: self.encoding,
+                fields: Vec::new(),
+            }),
+        }
+    }
+
+    /// Convert binary data of a column into JSON; UUID columns are written in their
+    /// canonical form whatever the encoding.
+    pub(crate) fn to_json(&self, data: &[u8], column: Option<&Type>) -> Value {
+        let is_uuid = column.is_some_and(|column| {
+            column.is_primitive()
+                && column.get_basic_info().logical_type() == Some(LogicalType::Uuid)
+        });
+        match is_uuid {
+            true if data.len() == 16 => Value::String(uuid_to_string(data)),
+            _ => self.encoding.to_json(data),
+        }
+    }
+
+    fn field_mut(&mut self, name: &str) -> &mut BytesEncodings {
+        let index = match self.fields.iter().position(|(field, _)| field == name) {
+            Some(index) => index,
+            None => {
+                let encodings = BytesEncodings {
+                    encoding: self.encoding,
+                    fields: Vec::new(),
+                };
+                self.fields.push((name.to_string(), encodings));
+                self.fields.len() - 1
+            }
+        };
+        &mut self.fields[index].1
+    }
+}
+
+fn parse_encoding(encoding: &str) -> Result<BytesEncoding> {
+    BytesEncoding::try_from(encoding.to_string())
+        .map_err(|e| eyre!("failed to parse bytes_encoding: {}", e))
+}
+
+/// Canonical `8-4-4-4-12` form of a UUID.
+fn uuid_to_string(data: &[u8]) -> String {
+    let hex = hex::encode(data);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::basic::{Repetition, Type as PhysicalType};
+    use serde_json::json;
+
+    const DATA: &[u8] = b"\xfbhi?";
+
+    #[test]
+    fn test_bytes_encodings() {
+        assert_eq!(BytesEncoding::Hex.to_json(DATA), json!("fb68693f"));
+        assert_eq!(BytesEncoding::Base64.to_json(DATA), json!("+2hpPw=="));
+        assert_eq!(BytesEncoding::Base64Url.to_json(DATA), json!("-2hpPw"));
+        assert_eq!(BytesEncoding::Utf8.to_json(DATA), json!("+2hpPw=="));
+        assert_eq!(BytesEncoding::Utf8.to_json("héllo".as_bytes()), json!("héllo"));
+    }
+
+    #[test]
+    fn test_column_overrides() {
+        let encodings =
+            BytesEncodings::parse("meta.raw=base64url, base64url, payload=utf8, meta=base64").unwrap();
+        assert_eq!(encodings.field("checksum").encoding, BytesEncoding::Base64Url);
+        assert_eq!(encodings.field("payload").encoding, BytesEncoding::Utf8);
+        assert_eq!(encodings.field("meta").field("raw").encoding, BytesEncoding::Base64Url);
+        assert_eq!(encodings.field("meta").field("signature").encoding, BytesEncoding::Base64);
+        // Paths only apply from the top-level
+        assert_eq!(encodings.field("checksum").field("payload").encoding, BytesEncoding::Base64Url);
+
+        assert!(BytesEncodings::parse("payload=base32").is_err());
+        assert!(BytesEncodings::parse("meta.=hex").is_err());
+        assert_eq!(
+            BytesEncodings::parse("hex,payload=utf8,base64")
+                .unwrap_err()
+                .to_string(),
+            "invalid `bytes_encoding` param: more than one default encoding in `hex,payload=utf8,base64`"
+        );
+    }
+
+    #[test]
+    fn test_uuid() {
+        let uuid = Type::primitive_type_builder("id", PhysicalType::FIXED_LEN_BYTE_ARRAY)
+            .with_repetition(Repetition::REQUIRED)
+            .with_length(16)
+            .with_logical_type(Some(LogicalType::Uuid))
+            .build()
+            .unwrap();
+        let data = hex::decode("550e8400e29b41d4a716446655440000").unwrap();
+
+        assert_eq!(
+            BytesEncodings::parse("base64").unwrap().to_json(&data, Some(&uuid)),
+            json!("550e8400-e29b-41d4-a716-446655440000")
+        );
+        assert_eq!(
+            BytesEncodings::default().to_json(&data, None),
+            json!("550e8400e29b41d4a716446655440000")
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use parquet::data_type::Decimal;
+use serde_json::{Number, Value};
+
+/// Widest precision whose decimals survive the round trip through a JSON (double) number.
+const MAX_NUMBER_PRECISION: i32 = 15;
+/// Widest precision of whole decimals that fit a 64-bit integer.
+const MAX_INTEGER_PRECISION: i32 = 18;
+
+/// How decimal columns are written.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) enum DecimalFormat {
+    /// JSON strings holding every digit, such as `"123.45"`.
+    #[default]
+    String,
+    /// JSON numbers when the column precision fits, strings otherwise.
+    Number,
+}
+
+impl TryFrom<String> for DecimalFormat {
+    type Error = &'static str;
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "" | "string" => Ok(DecimalFormat::String),
+            "number" => Ok(DecimalFormat::Number),
+            _ => Err("Invalid decimal format"),
+        }
+    }
+}
+
+/// Convert a decimal into JSON, applying its scale.
+///
+/// With `DecimalFormat::Number`, whole decimals of precision up to 18 become integers and
+/// decimals of precision up to 15 become floating point numbers. The choice depends on the
+/// column precision rather than on the value, so a column keeps a single JSON type.
+pub(crate) fn decimal_to_json(decimal: &Decimal, format: DecimalFormat) -> Value {
+    let text = decimal_to_string(decimal.data(), decimal.scale());
+
+    if let DecimalFormat::Number = format {
+        let number = if decimal.scale() == 0 && decimal.precision() <= MAX_INTEGER_PRECISION {
+            text.parse::<i64>().ok().map(Number::from)
+        } else if decimal.precision() <= MAX_NUMBER_PRECISION {
+            text.parse::<f64>().ok().and_then(Number::from_f64)
+        } else {
+            None
+        };
+        if let Some(number) = number {
+            return Value::Number(number);
+        }
+    }
+
+    Value::String(text)
+}
+
+/// Format the unscaled value, a big-endian two's complement integer of any width, as a
+/// decimal string with `scale` digits after the point.
+fn decimal_to_string(unscaled: &[u8], scale: i32) -> String {
+    let negative = unscaled.first().is_some_and(|byte| byte & 0x80 != 0);
+    let magnitude = if negative {
+        negate(unscaled)
+    } else {
+        unscaled.to_vec()
+    };
+    let mut digits = to_decimal_digits(magnitude);
+
+    if scale < 0 {
+        if digits != "0" {
+            digits.push_str(&"0".repeat(scale.unsigned_abs() as usize));
+        }
+    } else if scale > 0 {
+        let scale = scale as usize;
+        if digits.len() <= scale {
+            digits.insert_str(0, &"0".repeat(scale + 1 - digits.len()));
+        }
+        digits.insert(digits.len() - scale, '.');
+    }
+
+    if negative {
+        digits.insert(0, '-');
+    }
+    digits
+}
+
+/// Two's complement negation of a big-endian integer.
+fn negate(value: &[u8]) -> Vec<u8> {
+    let mut negated: Vec<u8> = value.iter().map(|byte| !byte).collect();
+    for byte in negated.iter_mut().rev() {
+        let (sum, overflow) = byte.overflowing_add(1);
+        *byte = sum;
+        if !overflow {
+            break;
+        }
+    }
+    negated
+}
+
+/// Base 10 digits of an unsigned big-endian integer.
+fn to_decimal_digits(mut magnitude: Vec<u8>) -> String {
+    const CHUNK: u64 = 1_000_000_000;
+
+    // Divide by 10^9 repeatedly, collecting 9 digits at a time from the right
+    let mut chunks = Vec::new();
+    while magnitude.iter().any(|byte| *byte != 0) {
+        let mut remainder = 0u64;
+        for byte in magnitude.iter_mut() {
+            let current = remainder * 256 + u64::from(*byte);
+            *byte = (current / CHUNK) as u8;
+            remainder = current % CHUNK;
+        }
+        chunks.push(remainder);
+    }
+
+    let Some(last) = chunks.pop() else {
+        return "0".to_string();
+    };
+    let mut digits = last.to_string();
+    for chunk in chunks.iter().rev() {
+        digits.push_str(&format!("{:09}", chunk));
+    }
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::data_type::ByteArray;
+    use serde_json::json;
+
+    fn bytes_decimal(value: i128, precision: i32, scale: i32) -> Decimal {
+        Decimal::from_bytes(ByteArray::from(value.to_be_bytes().to_vec()), precision, scale)
+    }
+
+    #[test]
+    fn test_decimal_strings() {
+        let string = |decimal: Decimal| decimal_to_json(&decimal, DecimalFormat::String);
+
+        assert_eq!(string(Decimal::from_i32(12345, 9, 2)), json!("123.45"));
+        assert_eq!(string(Decimal::from_i32(-5, 9, 2)), json!("-0.05"));
+        assert_eq!(string(Decimal::from_i32(1200, 9, 2)), json!("12.00"));
+        assert_eq!(string(Decimal::from_i32(0, 9, 3)), json!("0.000"));
+        assert_eq!(string(Decimal::from_i64(i64::MIN, 18, 0)), json!("-9223372036854775808"));
+        assert_eq!(string(Decimal::from_i64(42, 18, -2)), json!("4200"));
+        assert_eq!(
+            string(bytes_decimal(12345678901234567890123456789012345678, 38, 10)),
+            json!("1234567890123456789012345678.9012345678")
+        );
+        assert_eq!(
+            string(bytes_decimal(-100000000000000000000, 38, 20)),
+            json!("-1.00000000000000000000")
+        );
+        assert_eq!(
+            string(Decimal::from_bytes(ByteArray::from(vec![0xff, 0x85]), 5, 1)),
+            json!("-12.3")
+        );
+    }
+
+    #[test]
+    fn test_decimal_numbers() {
+        let number = |decimal: Decimal| decimal_to_json(&decimal, DecimalFormat::Number);
+
+        assert_eq!(number(Decimal::from_i32(12345, 9, 2)), json!(123.45));
+        assert_eq!(number(Decimal::from_i64(-987654321, 18, 0)), json!(-987654321));
+        assert_eq!(number(bytes_decimal(-5, 5, 3)), json!(-0.005));
+        // Too wide for a double: kept as a string
+        assert_eq!(
+            number(Decimal::from_i64(123456789012345678, 18, 4)),
+            json!("12345678901234.5678")
+        );
+        assert_eq!(
+            number(bytes_decimal(12345678901234567890123456789, 38, 0)),
+            json!("12345678901234567890123456789")
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use std::cmp::Ordering;
+
+use chrono::{DateTime, NaiveDate};
+use fluvio_smartmodule::{eyre, Result};
+use parquet::basic::{ConvertedType, LogicalType, TimeUnit, Type as PhysicalType};
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::statistics::Statistics;
+use parquet::record::{Field, Row};
+use parquet::schema::types::{ColumnDescriptor, SchemaDescriptor};
+use serde::Deserialize;
+use serde_json::Value;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// Row predicates; a row is converted only when all of them match.
+#[derive(Debug, Default)]
+pub(crate) struct RowFilter {
+    predicates: Vec<PredicateSpec>,
+}
+
+/// JSON form of a predicate in the `filter` param.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PredicateSpec {
+    column: String,
+    op: Op,
+    value: Value,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl RowFilter {
+    /// Parse the JSON `filter` param: a predicate or a list of predicates.
+    pub(crate) fn parse(raw: &str) -> Result<Self> {
+        let parse_err = |err: serde_json::Error| eyre!("cannot parse `filter` param: {}", err);
+        let predicates: Vec<PredicateSpec> = match serde_json::from_str(raw).map_err(parse_err)? {
+            Value::Array(items) => items
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<serde_json::Result<_>>()
+                .map_err(parse_err)?,
+            value => vec![serde_json::from_value(value).map_err(parse_err)?],
+        };
+
+        if let Some(predicate) = predicates
+            .iter()
+            .find(|predicate| predicate.column.split('.').any(str::is_empty))
+        {
+            return Err(eyre!(
+                "invalid `filter` param: empty name in column `{}`",
+                predicate.column
+            ));
+        }
+
+        Ok(Self { predicates })
+    }
+
+    /// Paths of the filter columns, which are decoded even when not selected by `columns`.
+    pub(crate) fn paths(&self) -> Vec<Vec<String>> {
+        self.predicates
+            .iter()
+            .map(|predicate| predicate.column.split('.').map(str::to_string).collect())
+            .collect()
+    }
+
+    /// Bind the predicates to the leaf columns of a file schema.
+    pub(crate) fn resolve(&self, schema: &SchemaDescriptor) -> Result<Vec<ColumnPredicate>> {
+        self.predicates
+            .iter()
+            .map(|predicate| ColumnPredicate::new(predicate, schema))
+            .collect()
+    }
+}
+
+/// How the values of a filter column are compared.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Bool,
+    Int,
+    Float,
+    Str,
+    /// Days since the epoch.
+    Date,
+    /// Nanoseconds per unit of the stored value.
+    Timestamp(i128),
+}
+
+/// A filter value, or a bound of the column statistics.
+#[derive(Debug, PartialEq, PartialOrd)]
+enum Scalar {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// UTF-8 bytes, compared byte-wise like parquet string statistics.
+    Str(Vec<u8>),
+    Date(i32),
+    /// Nanoseconds since the epoch.
+    Timestamp(i128),
+}
+
+/// A predicate bound to a leaf column of a file.
+#[derive(Debug)]
+pub(crate) struct ColumnPredicate {
+    /// Index of the leaf column, which is also its index in the row group metadata.
+    index: usize,
+    path: Vec<String>,
+    kind: Kind,
+    op: Op,
+    value: Scalar,
+    /// Whether the min/max statistics of the column follow the order of `kind`.
+    use_statistics: bool,
+}
+
+impl ColumnPredicate {
+    fn new(spec: &PredicateSpec, schema: &SchemaDescriptor) -> Result<Self> {
+        let column = &spec.column;
+        let (index, descr) = schema
+            .columns()
+            .iter()
+            .enumerate()
+            .find(|(_, descr)| descr.path().string() == *column)
+            .ok_or_else(|| {
+                let prefix = format!("{}.", column);
+                if schema
+                    .columns()
+                    .iter()
+                    .any(|descr| descr.path().string().starts_with(&prefix))
+                {
+                    eyre!("filter column `{}` is a group, not a primitive column", column)
+                } else {
+                    eyre!("filter column `{}` not found in parquet schema", column)
+                }
+            })?;
+
+        if descr.max_rep_level() > 0 {
+            return Err(eyre!(
+                "filter column `{}` is inside a repeated field",
+                column
+            ));
+        }
+        let (kind, use_statistics) = column_kind(descr)
+            .ok_or_else(|| eyre!("filter column `{}` has an unsupported type", column))?;
+        let value = kind.parse(&spec.value).ok_or_else(|| {
+            eyre!(
+                "invalid filter on `{}`: expected {} value",
+                column,
+                kind.expected()
+            )
+        })?;
+
+        Ok(Self {
+            index,
+            path: column.split('.').map(str::to_string).collect(),
+            kind,
+            op: spec.op,
+            value,
+            use_statistics,
+        })
+    }
+
+    /// Whether some row of the row group may match, according to its statistics.
+    ///
+    /// Row groups without usable statistics may always match.
+    pub(crate) fn may_match(&self, row_group: &RowGroupMetaData) -> bool {
+        let Some(statistics) = row_group.column(self.index).statistics() else {
+            return true;
+        };
+
+        // Null values never match
+        let num_rows = row_group.num_rows() as u64;
+        if num_rows > 0 && statistics.null_count_opt() == Some(num_rows) {
+            return false;
+        }
+
+        let Some((min, max)) = self.bounds(statistics) else {
+            return true;
+        };
+        if min.partial_cmp(&max).is_none() {
+            return true;
+        }
+
+        let value = &self.value;
+        match self.op {
+            Op::Eq => min <= *value && max >= *value,
+            Op::Ne => !(min == *value && max == *value),
+            Op::Gt => max > *value,
+            Op::Ge => max >= *value,
+            Op::Lt => min < *value,
+            Op::Le => min <= *value,
+        }
+    }
+
+    /// Whether the row matches; a null or missing value never does.
+    pub(crate) fn matches(&self, row: &Row) -> bool {
+        let ordering = lookup(row, &self.path).and_then(|field| self.compare(field));
+        match (self.op, ordering) {
+            (_, None) => false,
+            (Op::Eq, Some(ordering)) => ordering == Ordering::Equal,
+            (Op::Ne, Some(ordering)) => ordering != Ordering::Equal,
+            (Op::Gt, Some(ordering)) => ordering == Ordering::Greater,
+            (Op::Ge, Some(ordering)) => ordering != Ordering::Less,
+            (Op::Lt, Some(ordering)) => ordering == Ordering::Less,
+            (Op::Le, Some(ordering)) => ordering != Ordering::Greater,
+        }
+    }
+
+    /// Compare a row value with the filter value.
+    fn compare(&self, field: &Field) -> Option<Ordering> {
+        match (&self.value, field) {
+            (Scalar::Bool(value), Field::Bool(v)) => v.partial_cmp(value),
+            (Scalar::Int(value), field) => int_value(field)?.partial_cmp(value),
+            (Scalar::Float(value), Field::Float(v)) => f64::from(*v).partial_cmp(value),
+            (Scalar::Float(value), Field::Float16(v)) => v.to_f64().partial_cmp(value),
+            (Scalar::Float(value), Field::Double(v)) => v.partial_cmp(value),
+            (Scalar::Str(value), Field::Str(v)) => v.as_bytes().partial_cmp(value.as_slice()),
+            (Scalar::Date(value), Field::Date(v)) => v.partial_cmp(value),
+            (Scalar::Timestamp(value), field) => {
+                let nanos = match (field, self.kind) {
+                    (Field::TimestampMillis(v), _) => i128::from(*v) * 1_000_000,
+                    (Field::TimestampMicros(v), _) => i128::from(*v) * 1_000,
+                    (Field::Long(v), Kind::Timestamp(unit)) => i128::from(*v) * unit,
+                    _ => return None,
+                };
+                nanos.partial_cmp(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Min and max of the column statistics, when they can be compared with the value.
+    fn bounds(&self, statistics: &Statistics) -> Option<(Scalar, Scalar)> {
+        if !self.use_statistics {
+            return None;
+        }
+        let bounds = match (self.kind, statistics) {
+            (Kind::Bool, Statistics::Boolean(s)) => {
+                (Scalar::Bool(*s.min_opt()?), Scalar::Bool(*s.max_opt()?))
+            }
+            (Kind::Int, Statistics::Int32(s)) => (
+                Scalar::Int(i64::from(*s.min_opt()?)),
+                Scalar::Int(i64::from(*s.max_opt()?)),
+            ),
+            (Kind::Int, Statistics::Int64(s)) => {
+                (Scalar::Int(*s.min_opt()?), Scalar::Int(*s.max_opt()?))
+            }
+            (Kind::Float, Statistics::Float(s)) => (
+                Scalar::Float(f64::from(*s.min_opt()?)),
+                Scalar::Float(f64::from(*s.max_opt()?)),
+            ),
+            (Kind::Float, Statistics::Double(s)) => {
+                (Scalar::Float(*s.min_opt()?), Scalar::Float(*s.max_opt()?))
+            }
+            // Legacy string statistics were ordered with signed bytes
+            (Kind::Str, Statistics::ByteArray(s)) if !statistics.is_min_max_deprecated() => (
+                Scalar::Str(s.min_opt()?.data().to_vec()),
+                Scalar::Str(s.max_opt()?.data().to_vec()),
+            ),
+            (Kind::Date, Statistics::Int32(s)) => {
+                (Scalar::Date(*s.min_opt()?), Scalar::Date(*s.max_opt()?))
+            }
+            (Kind::Timestamp(unit), Statistics::Int64(s)) => (
+                Scalar::Timestamp(i128::from(*s.min_opt()?) * unit),
+                Scalar::Timestamp(i128::from(*s.max_opt()?) * unit),
+            ),
+            _ => return None,
+        };
+        Some(bounds)
+    }
+}
+
+impl Kind {
+    /// Convert the JSON filter value into a value of this kind.
+    fn parse(self, value: &Value) -> Option<Scalar> {
+        match (self, value) {
+            (Kind::Bool, Value::Bool(v)) => Some(Scalar::Bool(*v)),
+            (Kind::Int, Value::Number(n)) => n.as_i64().map(Scalar::Int),
+            (Kind::Int, Value::String(s)) => s.trim().parse().ok().map(Scalar::Int),
+            (Kind::Float, Value::Number(n)) => n.as_f64().map(Scalar::Float),
+            (Kind::Float, Value::String(s)) => s.trim().parse().ok().map(Scalar::Float),
+            (Kind::Str, Value::String(s)) => Some(Scalar::Str(s.as_bytes().to_vec())),
+            (Kind::Date, Value::String(s)) => {
+                let days = parse_date(s)?.signed_duration_since(NaiveDate::default());
+                i32::try_from(days.num_days()).ok().map(Scalar::Date)
+            }
+            (Kind::Timestamp(_), Value::String(s)) => parse_timestamp(s).map(Scalar::Timestamp),
+            _ => None,
+        }
+    }
+
+    /// Description of the expected filter value, for error messages.
+    fn expected(self) -> &'static str {
+        match self {
+            Kind::Bool => "a boolean",
+            Kind::Int => "an integer",
+            Kind::Float => "a numeric",
+            Kind::Str => "a string",
+            Kind::Date => "a `YYYY-MM-DD` date",
+            Kind::Timestamp(_) => "an RFC 3339 timestamp or `YYYY-MM-DD` date",
+        }
+    }
+}
+
+/// Comparison kind of a leaf column, and whether its statistics can be used.
+///
+/// Unsigned integers are compared by value but their statistics may follow the signed
+/// order of older writers, and INT96 timestamps have no meaningful statistics.
+fn column_kind(descr: &ColumnDescriptor) -> Option<(Kind, bool)> {
+    let logical_type = descr.logical_type();
+    let kind = match (descr.physical_type(), &logical_type, descr.converted_type()) {
+        (PhysicalType::BOOLEAN, _, _) => (Kind::Bool, true),
+        (PhysicalType::INT32, _, ConvertedType::DATE) => (Kind::Date, true),
+        (PhysicalType::INT64, Some(LogicalType::Timestamp { unit, .. }), _) => {
+            let nanos_per_unit = match unit {
+                TimeUnit::MILLIS(_) => 1_000_000,
+                TimeUnit::MICROS(_) => 1_000,
+                TimeUnit::NANOS(_) => 1,
+            };
+            (Kind::Timestamp(nanos_per_unit), true)
+        }
+        (PhysicalType::INT64, _, ConvertedType::TIMESTAMP_MILLIS) => {
+            (Kind::Timestamp(1_000_000), true)
+        }
+        (PhysicalType::INT64, _, ConvertedType::TIMESTAMP_MICROS) => {
+            (Kind::Timestamp(1_000), true)
+        }
+        (PhysicalType::INT96, _, _) => (Kind::Timestamp(1_000_000), false),
+        (PhysicalType::INT32 | PhysicalType::INT64, None, ConvertedType::NONE) => {
+            (Kind::Int, true)
+        }
+        (
+            PhysicalType::INT32 | PhysicalType::INT64,
+            _,
+            ConvertedType::INT_8
+            | ConvertedType::INT_16
+            | ConvertedType::INT_32
+            | ConvertedType::INT_64,
+        ) => (Kind::Int, true),
+        (
+            PhysicalType::INT32 | PhysicalType::INT64,
+            _,
+            ConvertedType::UINT_8
+            | ConvertedType::UINT_16
+            | ConvertedType::UINT_32
+            | ConvertedType::UINT_64,
+        ) => (Kind::Int, false),
+        (PhysicalType::FLOAT | PhysicalType::DOUBLE, _, _) => (Kind::Float, true),
+        (
+            PhysicalType::BYTE_ARRAY,
+            _,
+            ConvertedType::UTF8 | ConvertedType::ENUM | ConvertedType::JSON,
+        ) => (Kind::Str, true),
+        (
+            PhysicalType::BYTE_ARRAY,
+            Some(LogicalType::String | LogicalType::Enum | LogicalType::Json),
+            _,
+        ) => (Kind::Str, true),
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Find the field at a dotted path of a row; `None` when a group on the way is null.
+fn lookup<'r>(row: &'r Row, path: &[String]) -> Option<&'r Field> {
+    let (first, rest) = path.split_first()?;
+    let (_, field) = row.get_column_iter().find(|(name, _)| *name == first)?;
+    match field {
+        _ if rest.is_empty() => Some(field),
+        Field::Group(group) => lookup(group, rest),
+        _ => None,
+    }
+}
+
+/// Integer value of a row field, when it fits an `i64`.
+fn int_value(field: &Field) -> Option<i64> {
+    match field {
+        Field::Byte(v) => Some(i64::from(*v)),
+        Field::Short(v) => Some(i64::from(*v)),
+        Field::Int(v) => Some(i64::from(*v)),
+        Field::Long(v) => Some(*v),
+        Field::UByte(v) => Some(i64::from(*v)),
+        Field::UShort(v) => Some(i64::from(*v)),
+        Field::UInt(v) => Some(i64::from(*v)),
+        Field::ULong(v) => i64::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok()
+}
+
+/// Nanoseconds since the epoch of an RFC 3339 timestamp, or of midnight UTC of a date.
+fn parse_timestamp(value: &str) -> Option<i128> {
+    let datetime = match DateTime::parse_from_rfc3339(value.trim()) {
+        Ok(datetime) => datetime.to_utc(),
+        Err(_) => parse_date(value)?.and_hms_opt(0, 0, 0)?.and_utc(),
+    };
+    Some(
+        i128::from(datetime.timestamp()) * NANOS_PER_SECOND
+            + i128::from(datetime.timestamp_subsec_nanos()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    fn events() -> SerializedFileReader<bytes::Bytes> {
+        let data = std::fs::read("test-data/events.parquet").expect("Failed to read file");
+        SerializedFileReader::new(bytes::Bytes::from(data)).unwrap()
+    }
+
+    /// Which row groups of the events file may match the filter.
+    fn row_groups(filter: &str) -> Vec<bool> {
+        let reader = events();
+        let metadata = reader.metadata();
+        let predicates = RowFilter::parse(filter)
+            .unwrap()
+            .resolve(metadata.file_metadata().schema_descr())
+            .unwrap();
+
+        metadata
+            .row_groups()
+            .iter()
+            .map(|row_group| predicates.iter().all(|p| p.may_match(row_group)))
+            .collect()
+    }
+
+    #[test]
+    fn test_row_group_pruning() {
+        assert_eq!(
+            row_groups(r#"{"column": "region", "op": "eq", "value": "eu"}"#),
+            vec![true, false, false]
+        );
+        assert_eq!(
+            row_groups(r#"{"column": "ts", "op": "ge", "value": "2024-03-02T12:00:00Z"}"#),
+            vec![false, true, true]
+        );
+        assert_eq!(
+            row_groups(
+                r#"[{"column": "ts", "op": "lt", "value": "2024-03-03"},
+                    {"column": "latency_ms", "op": "gt", "value": 100}]"#
+            ),
+            vec![false, true, false]
+        );
+        // Not every row of the first row group is in `eu`
+        assert_eq!(
+            row_groups(r#"{"column": "region", "op": "ne", "value": "eu"}"#),
+            vec![true, true, true]
+        );
+    }
+
+    #[test]
+    fn test_resolve_errors() {
+        let reader = events();
+        let schema = reader.metadata().file_metadata().schema_descr();
+        let resolve = |filter: &str| {
+            RowFilter::parse(filter)
+                .unwrap()
+                .resolve(schema)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            resolve(r#"{"column": "zone", "op": "eq", "value": "eu"}"#),
+            "filter column `zone` not found in parquet schema"
+        );
+        assert_eq!(
+            resolve(r#"{"column": "ts", "op": "ge", "value": 1709251200000}"#),
+            "invalid filter on `ts`: expected an RFC 3339 timestamp or `YYYY-MM-DD` date value"
+        );
+        assert_eq!(
+            resolve(r#"{"column": "event_id", "op": "gt", "value": 1.5}"#),
+            "invalid filter on `event_id`: expected an integer value"
+        );
+        assert!(RowFilter::parse(r#"{"column": "ts", "op": "between", "value": 1}"#).is_err());
+        assert!(RowFilter::parse(r#"{"column": "location.", "op": "eq", "value": 1}"#).is_err());
+    }
+}
@@ -1,48 +1,173 @@
+use std::sync::{Arc, OnceLock};
+
 use fluvio_smartmodule::{smartmodule, Result, eyre, SmartModuleRecord, RecordData};
+use fluvio_smartmodule::dataplane::smartmodule::SmartModuleExtraParams;
 use serde_json::Value;
-use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::basic::{ConvertedType, Repetition};
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::properties::ReaderProperties;
+use parquet::file::reader::{FileReader, RowGroupReader, SerializedFileReader};
+use parquet::file::serialized_reader::SerializedRowGroupReader;
 use parquet::record::{Field, List, Map, Row};
-use parquet::data_type::Decimal;
+use parquet::schema::types::{SchemaDescriptor, Type};
 use bytes::Bytes;
-use chrono::{NaiveDate, DateTime};
+use chrono::NaiveDate;
+
+mod binary;
+mod decimal;
+mod filter;
+mod projection;
+mod temporal;
+
+use binary::BytesEncodings;
+use decimal::DecimalFormat;
+use filter::RowFilter;
+use projection::Projection;
+use temporal::{Temporal, TimestampFormat, Unit};
+
+static PARAMS: OnceLock<Params> = OnceLock::new();
+const DECIMAL_FORMAT_PARAM_NAME: &str = "decimal_format";
+const COLUMNS_PARAM_NAME: &str = "columns";
+const FILTER_PARAM_NAME: &str = "filter";
+const TIMESTAMP_FORMAT_PARAM_NAME: &str = "timestamp_format";
+const BYTES_ENCODING_PARAM_NAME: &str = "bytes_encoding";
 
 #[smartmodule(array_map)]
 pub fn array_map(record: &SmartModuleRecord) -> Result<Vec<(Option<RecordData>, RecordData)>> {
-    let result = process_parquet_data(record.value.as_ref())?;
+    let params = PARAMS.get().expect("params is not initialized");
+
+    let result = process_parquet_data(record.value.as_ref(), params)?;
     Ok(result)
 }
 
+#[smartmodule(init)]
+fn init(params: SmartModuleExtraParams) -> Result<()> {
+    let decimal_format_param = match params.get(DECIMAL_FORMAT_PARAM_NAME) {
+        Some(v) => DecimalFormat::try_from(v.to_string())
+            .map_err(|e| eyre!("failed to parse decimal_format: {}", e))?,
+        None => DecimalFormat::String,
+    };
+
+    let columns_param = match params.get(COLUMNS_PARAM_NAME) {
+        Some(v) => Projection::parse(v)?,
+        None => Projection::default(),
+    };
+
+    let filter_param = match params.get(FILTER_PARAM_NAME) {
+        Some(v) => RowFilter::parse(v)?,
+        None => RowFilter::default(),
+    };
+
+    let timestamp_format_param = match params.get(TIMESTAMP_FORMAT_PARAM_NAME) {
+        Some(v) => TimestampFormat::try_from(v.to_string())
+            .map_err(|e| eyre!("failed to parse timestamp_format: {}", e))?,
+        None => TimestampFormat::Display,
+    };
+
+    let bytes_encoding_param = match params.get(BYTES_ENCODING_PARAM_NAME) {
+        Some(v) => BytesEncodings::parse(v)?,
+        None => BytesEncodings::default(),
+    };
+
+    PARAMS
+        .set(Params {
+            decimal_format: decimal_format_param,
+            columns: columns_param,
+            filter: filter_param,
+            timestamp_format: timestamp_format_param,
+            bytes_encoding: bytes_encoding_param,
+        })
+        .expect("params is already initialized");
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct Params {
+    decimal_format: DecimalFormat,
+    columns: Projection,
+    filter: RowFilter,
+    timestamp_format: TimestampFormat,
+    bytes_encoding: BytesEncodings,
+}
+
 /// Process Parquet data from a byte slice and produce JSON records
-fn process_parquet_data(data: &[u8]) -> Result<Vec<(Option<RecordData>, RecordData)>> {
+fn process_parquet_data(data: &[u8], params: &Params) -> Result<Vec<(Option<RecordData>, RecordData)>> {
     // Convert the data slice to Bytes
     let bytes = Bytes::copy_from_slice(data);
 
     // Initialize a Parquet reader from Bytes
-    let reader = SerializedFileReader::new(bytes)
+    let reader = SerializedFileReader::new(bytes.clone())
         .map_err(|e| eyre!("Failed to read Parquet data: {}", e))?;
 
+    let metadata = reader.metadata();
+    let schema = metadata.file_metadata().schema();
+    let predicates = params.filter.resolve(metadata.file_metadata().schema_descr())?;
+
+    // Time of day columns are read as plain integers, then converted with the file schema
+    let readable_schema = Arc::new(SchemaDescriptor::new(temporal::readable_schema(
+        &metadata.file_metadata().schema_descr().root_schema_ptr(),
+    )?));
+
+    // Only decode the selected columns, along with the filter columns
+    let filter_paths = params.filter.paths();
+    let projection = params
+        .columns
+        .schema(readable_schema.root_schema(), &filter_paths)?;
+
+    let chunk_reader = Arc::new(bytes);
+    let properties = Arc::new(ReaderProperties::builder().build());
     let mut records = Vec::new();
 
-    // Iterate over rows and convert each to JSON
-    for row_result in reader.get_row_iter(None)
-        .map_err(|e| eyre!("Failed to iterate over rows: {}", e))? 
-    {
-        let row = row_result.map_err(|e| eyre!("Error reading row: {}", e))?;
-        let json_value = row_to_json(&row);
-        let json_string = serde_json::to_string(&json_value)
-            .map_err(|e| eyre!("Failed to serialize JSON: {}", e))?;
-        records.push((None, RecordData::from(json_string)));
+    for (index, row_group) in metadata.row_groups().iter().enumerate() {
+        // Skip row groups whose statistics rule out a match
+        if !predicates.iter().all(|predicate| predicate.may_match(row_group)) {
+            continue;
+        }
+        let row_group = RowGroupMetaData::from_thrift(readable_schema.clone(), row_group.to_thrift())
+            .map_err(|e| eyre!("Failed to read row group {}: {}", index, e))?;
+        let row_group_reader =
+            SerializedRowGroupReader::new(chunk_reader.clone(), &row_group, None, properties.clone())
+                .map_err(|e| eyre!("Failed to read row group {}: {}", index, e))?;
+
+        // Iterate over rows and convert each to JSON
+        for row_result in row_group_reader.get_row_iter(projection.clone())
+            .map_err(|e| eyre!("Failed to iterate over rows: {}", e))?
+        {
+            let row = row_result.map_err(|e| eyre!("Error reading row: {}", e))?;
+            if !predicates.iter().all(|predicate| predicate.matches(&row)) {
+                continue;
+            }
+            let mut json_value = row_to_json(&row, Some(schema), params);
+            params.columns.strip(&mut json_value, &filter_paths);
+            let json_string = serde_json::to_string(&json_value)
+                .map_err(|e| eyre!("Failed to serialize JSON: {}", e))?;
+            records.push((None, RecordData::from(json_string)));
+        }
     }
 
     Ok(records)
 }
 
 /// Convert a Parquet row to JSON
-fn row_to_json(row: &Row) -> Value {
+///
+/// The `group` type of the row, when known, gives the temporal and UUID types of its
+/// columns.
+fn row_to_json(row: &Row, group: Option<&Type>, params: &Params) -> Value {
+    fields_to_json(row, group, &params.bytes_encoding, params)
+}
+
+/// Convert the columns of a row or nested group to JSON, with `bytes` the encodings of
+/// their binary values.
+fn fields_to_json(row: &Row, group: Option<&Type>, bytes: &BytesEncodings, params: &Params) -> Value {
     let mut json_object = serde_json::Map::new();
 
     for (key, field) in row.get_column_iter() {
-        let json_value = field_to_json(&field);
+        let column = group
+            .filter(|group| group.is_group())
+            .and_then(|group| group.get_fields().iter().find(|child| child.name() == key))
+            .map(|child| child.as_ref());
+        let json_value = field_to_json(field, column, &bytes.field(key), params);
         json_object.insert(key.to_string(), json_value);
     }
 
@@ -50,14 +175,21 @@ fn row_to_json(row: &Row) -> Value {
 }
 
 /// Convert individual Field to JSON
-fn field_to_json(field: &Field) -> Value {
+fn field_to_json(field: &Field, column: Option<&Type>, bytes: &BytesEncodings, params: &Params) -> Value {
+    let temporal = column.and_then(Temporal::of);
     match field {
         Field::Null => Value::Null,
         Field::Bool(v) => Value::Bool(*v),
         Field::Byte(v) => Value::Number((*v as i64).into()),
         Field::Short(v) => Value::Number((*v as i64).into()),
-        Field::Int(v) => Value::Number((*v as i64).into()),
-        Field::Long(v) => Value::Number((*v).into()),
+        Field::Int(v) => match temporal {
+            Some(temporal) => temporal.to_json(*v as i64, &params.timestamp_format),
+            None => Value::Number((*v as i64).into()),
+        },
+        Field::Long(v) => match temporal {
+            Some(temporal) => temporal.to_json(*v, &params.timestamp_format),
+            None => Value::Number((*v).into()),
+        },
         Field::UByte(v) => Value::Number((*v as u64).into()),
         Field::UShort(v) => Value::Number((*v as u64).into()),
         Field::UInt(v) => Value::Number((*v as u64).into()),
@@ -72,31 +204,19 @@ fn field_to_json(field: &Field) -> Value {
             .map(Value::Number)
             .unwrap_or(Value::Null),
         Field::Str(v) => Value::String(v.clone()),
-        Field::Bytes(v) => Value::String(hex::encode(v)),
-        Field::Decimal(decimal) => decimal_to_json(decimal),
+        Field::Bytes(v) => bytes.to_json(v.data(), column),
+        Field::Decimal(decimal) => decimal::decimal_to_json(decimal, params.decimal_format),
         Field::Date(days) => date_to_json(*days),
-        Field::TimestampMillis(ts) => timestamp_to_json(*ts, 1_000),
-        Field::TimestampMicros(ts) => timestamp_to_json(*ts, 1_000_000),
-        Field::MapInternal(map) => map_to_json(map),
-        Field::Group(group) => group_to_json(group),
-        Field::ListInternal(list) => list_to_json(list),
-    }
-}
-
-/// Convert Decimal to JSON
-fn decimal_to_json(decimal: &Decimal) -> Value {
-    match decimal {
-        Decimal::Int32 { value, .. } => {
-            let num = i32::from_be_bytes(*value);
-            Value::String(num.to_string())
-        }
-        Decimal::Int64 { value, .. } => {
-            let num = i64::from_be_bytes(*value);
-            Value::String(num.to_string())
-        }
-        Decimal::Bytes { value, .. } => {
-            Value::String(format!("0x{}", hex::encode(value)))
-        }
+        // INT96 timestamps are read as milliseconds and adjusted to UTC
+        Field::TimestampMillis(ts) => temporal
+            .unwrap_or(Temporal::Timestamp { unit: Unit::Millis, utc: true })
+            .to_json(*ts, &params.timestamp_format),
+        Field::TimestampMicros(ts) => temporal
+            .unwrap_or(Temporal::Timestamp { unit: Unit::Micros, utc: true })
+            .to_json(*ts, &params.timestamp_format),
+        Field::MapInternal(map) => map_to_json(map, column, bytes, params),
+        Field::Group(group) => group_to_json(group, column, bytes, params),
+        Field::ListInternal(list) => list_to_json(list, column, bytes, params),
     }
 }
 
@@ -108,38 +228,71 @@ fn date_to_json(days: i32) -> Value {
     }
 }
 
-/// Convert Timestamp to JSON
-fn timestamp_to_json(timestamp: i64, divisor: i64) -> Value {
-    let naive_datetime = DateTime::from_timestamp(
-        timestamp / divisor,
-        ((timestamp % divisor) * 1_000_000) as u32, // Nanoseconds as i32
-    );
-    match naive_datetime {
-        Some(datetime) => Value::String(datetime.to_string()),
-        None => Value::Null,
-    }
-}
-
 /// Convert Map to JSON
-fn map_to_json(map: &Map) -> Value {
+fn map_to_json(map: &Map, column: Option<&Type>, bytes: &BytesEncodings, params: &Params) -> Value {
+    let value_type = column.and_then(map_value_type);
     let mut json_object = serde_json::Map::new();
     for (key, value) in map.entries() {
-        json_object.insert(key.to_string(), field_to_json(value));
+        json_object.insert(key.to_string(), field_to_json(value, value_type, bytes, params));
     }
     Value::Object(json_object)
 }
 
 /// Convert Group to JSON
-fn group_to_json(group: &Row) -> Value {
-    row_to_json(group) // Recursively process nested rows
+fn group_to_json(group: &Row, column: Option<&Type>, bytes: &BytesEncodings, params: &Params) -> Value {
+    fields_to_json(group, column, bytes, params) // Recursively process nested rows
 }
 
 /// Convert List to JSON
-fn list_to_json(list: &List) -> Value {
-    let json_array: Vec<Value> = list.elements().iter().map(field_to_json).collect();
+fn list_to_json(list: &List, column: Option<&Type>, bytes: &BytesEncodings, params: &Params) -> Value {
+    let element_type = column.and_then(list_element_type);
+    let json_array: Vec<Value> = list
+        .elements()
+        .iter()
+        .map(|field| field_to_json(field, element_type, bytes, params))
+        .collect();
     Value::Array(json_array)
 }
 
+/// Type of the elements of a list, following the list layouts of the row reader.
+fn list_element_type(list: &Type) -> Option<&Type> {
+    let info = list.get_basic_info();
+    // A repeated field is a list of its own type
+    if info.has_repetition() && info.repetition() == Repetition::REPEATED {
+        return Some(list);
+    }
+    if list.is_primitive() || info.converted_type() != ConvertedType::LIST {
+        return None;
+    }
+
+    let repeated = list.get_fields().first()?;
+    // Legacy two-level lists repeat the element itself
+    let is_element = repeated.is_primitive()
+        || repeated.get_fields().len() > 1
+        || repeated.name() == "array"
+        || repeated.name().ends_with("_tuple");
+    if is_element {
+        Some(repeated)
+    } else {
+        repeated.get_fields().first().map(|element| element.as_ref())
+    }
+}
+
+/// Type of the values of a map.
+fn map_value_type(map: &Type) -> Option<&Type> {
+    let converted_type = map.get_basic_info().converted_type();
+    if map.is_primitive()
+        || !matches!(converted_type, ConvertedType::MAP | ConvertedType::MAP_KEY_VALUE)
+    {
+        return None;
+    }
+    let key_value = map.get_fields().first()?;
+    if key_value.is_primitive() {
+        return None;
+    }
+    key_value.get_fields().get(1).map(|value| value.as_ref())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,7 +306,7 @@ mod tests {
             .into_iter()
             .map(|(_, record_data)| {
                 // Convert RecordData into JSON object
-                serde_json::from_slice::<Value>(&record_data.to_vec()).unwrap()
+                serde_json::from_slice::<Value>(record_data.as_ref()).unwrap()
             })
             .collect();
         result_json
@@ -167,7 +320,7 @@ mod tests {
         parquet_file.read_to_end(&mut parquet_data).expect("Failed to read file");
 
         // Call the function under test
-        let records = process_parquet_data(&parquet_data)?;
+        let records = process_parquet_data(&parquet_data, &Params::default())?;
         let computed_output = convert_records_to_json(records);
         let computer_str = serde_json::to_string_pretty(&computed_output).expect("Failed to serialize vec1");
 
@@ -180,4 +333,137 @@ mod tests {
         assert_eq!(computer_str, expected_str);
         Ok(())
     }
+
+    #[test]
+    fn test_process_parquet_data_with_decimals() -> Result<()> {
+        let parquet_data = fs::read("test-data/invoices.parquet").expect("Failed to read file");
+        let cases = [
+            (DecimalFormat::String, "test-data/invoices.json"),
+            (DecimalFormat::Number, "test-data/invoices-number.json"),
+        ];
+
+        for (decimal_format, output_file_path) in cases {
+            let params = Params {
+                decimal_format,
+                ..Params::default()
+            };
+
+            // Call the function under test
+            let records = process_parquet_data(&parquet_data, &params)?;
+            let computed_output = convert_records_to_json(records);
+
+            // Prepare Result
+            let output_file = fs::read_to_string(output_file_path).unwrap();
+            let expected_output: Vec<Value> = serde_json::from_str(&output_file).expect("Failed to parse JSON");
+
+            assert_eq!(computed_output, expected_output);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_parquet_data_with_projection() -> Result<()> {
+        let parquet_data = fs::read("test-data/telemetry.parquet").expect("Failed to read file");
+        let params = Params {
+            columns: Projection::parse("device_id,location.lat,location.accuracy.vertical")?,
+            ..Params::default()
+        };
+
+        // Call the function under test
+        let records = process_parquet_data(&parquet_data, &params)?;
+        let computed_output = convert_records_to_json(records);
+
+        // Prepare Result
+        let output_file = fs::read_to_string("test-data/telemetry-projected.json").unwrap();
+        let expected_output: Vec<Value> = serde_json::from_str(&output_file).expect("Failed to parse JSON");
+
+        assert_eq!(computed_output, expected_output);
+
+        let params = Params {
+            columns: Projection::parse("location.altitude")?,
+            ..Params::default()
+        };
+        let err = process_parquet_data(&parquet_data, &params).unwrap_err();
+        assert_eq!(err.to_string(), "column `location.altitude` not found in parquet schema");
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_parquet_data_with_filter() -> Result<()> {
+        let parquet_data = fs::read("test-data/events.parquet").expect("Failed to read file");
+        let params = Params {
+            columns: Projection::parse("event_id,region,ok")?,
+            filter: RowFilter::parse(
+                r#"[{"column": "region", "op": "ne", "value": "eu"},
+                    {"column": "latency_ms", "op": "ge", "value": 90}]"#,
+            )?,
+            ..Params::default()
+        };
+
+        // Call the function under test
+        let records = process_parquet_data(&parquet_data, &params)?;
+        let computed_output = convert_records_to_json(records);
+
+        // Prepare Result
+        let output_file = fs::read_to_string("test-data/events-filtered.json").unwrap();
+        let expected_output: Vec<Value> = serde_json::from_str(&output_file).expect("Failed to parse JSON");
+
+        assert_eq!(computed_output, expected_output);
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_parquet_data_with_bytes_encoding() -> Result<()> {
+        let parquet_data = fs::read("test-data/attachments.parquet").expect("Failed to read file");
+        let cases = [
+            ("hex", "test-data/attachments.json"),
+            ("base64url,content=utf8,meta=base64", "test-data/attachments-overrides.json"),
+        ];
+
+        for (bytes_encoding, output_file_path) in cases {
+            let params = Params {
+                bytes_encoding: BytesEncodings::parse(bytes_encoding)?,
+                ..Params::default()
+            };
+
+            // Call the function under test
+            let records = process_parquet_data(&parquet_data, &params)?;
+            let computed_output = convert_records_to_json(records);
+
+            // Prepare Result
+            let output_file = fs::read_to_string(output_file_path).unwrap();
+            let expected_output: Vec<Value> = serde_json::from_str(&output_file).expect("Failed to parse JSON");
+
+            assert_eq!(computed_output, expected_output);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_parquet_data_with_timestamps() -> Result<()> {
+        let parquet_data = fs::read("test-data/timestamps.parquet").expect("Failed to read file");
+        let cases = [
+            (TimestampFormat::Display, "test-data/timestamps.json"),
+            (TimestampFormat::Rfc3339, "test-data/timestamps-rfc3339.json"),
+            (TimestampFormat::EpochMicros, "test-data/timestamps-epoch-micros.json"),
+        ];
+
+        for (timestamp_format, output_file_path) in cases {
+            let params = Params {
+                timestamp_format,
+                ..Params::default()
+            };
+
+            // Call the function under test
+            let records = process_parquet_data(&parquet_data, &params)?;
+            let computed_output = convert_records_to_json(records);
+
+            // Prepare Result
+            let output_file = fs::read_to_string(output_file_path).unwrap();
+            let expected_output: Vec<Value> = serde_json::from_str(&output_file).expect("Failed to parse JSON");
+
+            assert_eq!(computed_output, expected_output);
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,260 @@
+use std::sync::Arc;
+
+use fluvio_smartmodule::{eyre, Result};
+use parquet::schema::types::{Type, TypePtr};
+use serde_json::Value;
+
+/// Columns to decode, as top-level names or dotted paths into nested groups.
+#[derive(Debug, Default)]
+pub(crate) struct Projection {
+    /// Selected paths; empty selects every column.
+    paths: Vec<Vec<String>>,
+}
+
+impl Projection {
+    /// Parse the `columns` param, given either as a JSON array or as comma-separated paths.
+    pub(crate) fn parse(value: &str) -> Result<Self> {
+        let list: Vec<String> = if value.trim_start().starts_with('[') {
+            serde_json::from_str(value)
+                .map_err(|err| eyre!("cannot parse `columns` param: {}", err))?
+        } else {
+            value.split(',').map(|item| item.trim().to_string()).collect()
+        };
+
+        let paths: Vec<Vec<String>> = list
+            .iter()
+            .map(|path| path.split('.').map(str::to_string).collect::<Vec<_>>())
+            .collect();
+        if paths.is_empty() || paths.iter().flatten().any(String::is_empty) {
+            return Err(eyre!("invalid `columns` param: empty name in `{}`", value));
+        }
+
+        Ok(Self { paths })
+    }
+
+    /// Build the projected schema of a file, keeping the file order of the fields.
+    ///
+    /// The `filter_paths` are decoded along with the selected columns, so the row filter
+    /// can read them. Returns `None` when every column is selected.
+    pub(crate) fn schema(
+        &self,
+        file_schema: &Type,
+        filter_paths: &[Vec<String>],
+    ) -> Result<Option<Type>> {
+        if self.paths.is_empty() {
+            return Ok(None);
+        }
+
+        let paths: Vec<&[String]> = self
+            .paths
+            .iter()
+            .chain(filter_paths)
+            .map(Vec::as_slice)
+            .collect();
+        let fields = prune(file_schema, &paths, &[])?;
+
+        let projected = Type::group_type_builder(file_schema.name())
+            .with_fields(fields)
+            .build()
+            .map_err(|err| eyre!("cannot build projected schema: {}", err))?;
+        Ok(Some(projected))
+    }
+
+    /// Remove from a converted row the fields decoded only for the row filter.
+    pub(crate) fn strip(&self, row: &mut Value, filter_paths: &[Vec<String>]) {
+        if self.paths.is_empty() {
+            return;
+        }
+
+        for filter_path in filter_paths {
+            // Depth of the deepest group shared with a selected path
+            let mut shared = 0;
+            for path in &self.paths {
+                let common = path
+                    .iter()
+                    .zip(filter_path)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if common == path.len() {
+                    // Selected along with an enclosing field
+                    shared = filter_path.len();
+                    break;
+                }
+                shared = shared.max(common);
+            }
+            if shared == filter_path.len() {
+                continue;
+            }
+
+            let target = filter_path[..shared]
+                .iter()
+                .try_fold(&mut *row, |target, key| target.get_mut(key));
+            if let Some(Value::Object(fields)) = target {
+                fields.remove(&filter_path[shared]);
+            }
+        }
+    }
+}
+
+/// Keep the fields of a group selected by `paths`, relative to the group at `parent`.
+///
+/// A path ending at a field keeps the whole field; a longer path keeps only the selected
+/// fields of a nested group.
+fn prune(group: &Type, paths: &[&[String]], parent: &[String]) -> Result<Vec<TypePtr>> {
+    if let Some(path) = paths.iter().find(|path| {
+        !group
+            .get_fields()
+            .iter()
+            .any(|field| field.name() == path[0])
+    }) {
+        let mut missing = parent.to_vec();
+        missing.push(path[0].clone());
+        return Err(eyre!("column `{}` not found in parquet schema", missing.join(".")));
+    }
+
+    let mut fields = Vec::new();
+    for field in group.get_fields() {
+        let selected: Vec<&[String]> = paths
+            .iter()
+            .filter(|path| path[0] == field.name())
+            .map(|path| &path[1..])
+            .collect();
+        if selected.is_empty() {
+            continue;
+        }
+        if selected.iter().any(|rest| rest.is_empty()) {
+            fields.push(field.clone());
+            continue;
+        }
+
+        let mut path = parent.to_vec();
+        path.push(field.name().to_string());
+        if field.is_primitive() {
+            return Err(eyre!(
+                "column `{}` is not a group, cannot select `{}`",
+                path.join("."),
+                selected[0][0]
+            ));
+        }
+
+        let info = field.get_basic_info();
+        let mut builder = Type::group_type_builder(field.name())
+            .with_converted_type(info.converted_type())
+            .with_logical_type(info.logical_type())
+            .with_id(info.has_id().then(|| info.id()))
+            .with_fields(prune(field, &selected, &path)?);
+        if info.has_repetition() {
+            builder = builder.with_repetition(info.repetition());
+        }
+        let pruned = builder
+            .build()
+            .map_err(|err| eyre!("cannot build projected schema: {}", err))?;
+        fields.push(Arc::new(pruned));
+    }
+
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::schema::parser::parse_message_type;
+    use parquet::schema::printer::print_schema;
+
+    const SCHEMA: &str = "
+        message telemetry {
+            REQUIRED BINARY device_id (STRING);
+            REQUIRED INT64 seq;
+            OPTIONAL GROUP location {
+                REQUIRED DOUBLE lat;
+                REQUIRED DOUBLE lon;
+                OPTIONAL GROUP accuracy {
+                    OPTIONAL FLOAT horizontal;
+                    OPTIONAL FLOAT vertical;
+                }
+            }
+        }";
+
+    fn projected(columns: &str) -> Result<String> {
+        let file_schema = parse_message_type(SCHEMA).unwrap();
+        let projected = Projection::parse(columns)?
+            .schema(&file_schema, &[])?
+            .expect("columns are selected");
+
+        let mut printed = Vec::new();
+        print_schema(&mut printed, &projected);
+        Ok(String::from_utf8(printed).unwrap())
+    }
+
+    fn schema_of(message: &str) -> String {
+        let mut printed = Vec::new();
+        print_schema(&mut printed, &parse_message_type(message).unwrap());
+        String::from_utf8(printed).unwrap()
+    }
+
+    #[test]
+    fn test_nested_projection() {
+        assert_eq!(
+            projected("location.accuracy.vertical, seq, location.lat").unwrap(),
+            schema_of(
+                "message telemetry {
+                    REQUIRED INT64 seq;
+                    OPTIONAL GROUP location {
+                        REQUIRED DOUBLE lat;
+                        OPTIONAL GROUP accuracy {
+                            OPTIONAL FLOAT vertical;
+                        }
+                    }
+                }"
+            )
+        );
+
+        // A whole group wins over paths into it
+        assert_eq!(
+            projected(r#"["location", "location.lat"]"#).unwrap(),
+            projected("location").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_projection() {
+        assert_eq!(
+            projected("location.altitude").unwrap_err().to_string(),
+            "column `location.altitude` not found in parquet schema"
+        );
+        assert_eq!(
+            projected("seq.value").unwrap_err().to_string(),
+            "column `seq` is not a group, cannot select `value`"
+        );
+        assert!(Projection::parse("seq,,lat").is_err());
+        assert!(Projection::parse("location.").is_err());
+        assert!(Projection::default()
+            .schema(&parse_message_type(SCHEMA).unwrap(), &[])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_strip_filter_columns() {
+        let projection = Projection::parse("device_id,location.accuracy").unwrap();
+        let filter_paths = vec![
+            vec!["seq".to_string()],
+            vec!["location".to_string(), "lat".to_string()],
+            vec!["location".to_string(), "accuracy".to_string(), "vertical".to_string()],
+        ];
+        let mut row = serde_json::json!({
+            "device_id": "th-01",
+            "seq": 1,
+            "location": {"lat": 52.52, "accuracy": {"horizontal": 3.5, "vertical": 7.0}},
+        });
+
+        projection.strip(&mut row, &filter_paths);
+        assert_eq!(
+            row,
+            serde_json::json!({
+                "device_id": "th-01",
+                "location": {"accuracy": {"horizontal": 3.5, "vertical": 7.0}},
+            })
+        );
+    }
+}
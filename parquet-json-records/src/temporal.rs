@@ -0,0 +1,347 @@
+use std::fmt::{Display, Write};
+use std::sync::Arc;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, NaiveTime, SecondsFormat};
+use fluvio_smartmodule::{eyre, Result};
+use parquet::basic::{ConvertedType, LogicalType, TimeUnit};
+use parquet::schema::types::{Type, TypePtr};
+use serde_json::Value;
+
+/// How timestamp and time of day columns are written.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) enum TimestampFormat {
+    /// The `chrono` display form, such as `"2024-03-01 08:15:30.123 UTC"`.
+    #[default]
+    Display,
+    /// RFC 3339 strings, such as `"2024-03-01T08:15:30.123Z"`.
+    Rfc3339,
+    /// Milliseconds since the epoch, or since midnight for times of day.
+    EpochMillis,
+    /// Microseconds since the epoch, or since midnight for times of day.
+    EpochMicros,
+    /// A `strftime` pattern, such as `"%Y-%m-%d %H:%M:%S"`.
+    Custom(String),
+}
+
+impl TryFrom<String> for TimestampFormat {
+    type Error = &'static str;
+    fn try_from(s: String) -> core::result::Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "" | "display" => Ok(TimestampFormat::Display),
+            "rfc3339" => Ok(TimestampFormat::Rfc3339),
+            "epoch_millis" => Ok(TimestampFormat::EpochMillis),
+            "epoch_micros" => Ok(TimestampFormat::EpochMicros),
+            _ if s.contains('%') && StrftimeItems::new(&s).all(|item| item != Item::Error) => {
+                Ok(TimestampFormat::Custom(s))
+            }
+            _ => Err("Invalid timestamp format"),
+        }
+    }
+}
+
+/// Unit of a stored timestamp or time of day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Unit {
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl Unit {
+    fn per_second(self) -> i64 {
+        match self {
+            Unit::Millis => 1_000,
+            Unit::Micros => 1_000_000,
+            Unit::Nanos => 1_000_000_000,
+        }
+    }
+
+    /// Convert a value into another unit, rounding down.
+    fn convert(self, value: i64, unit: Unit) -> Option<i64> {
+        let (from, to) = (self.per_second(), unit.per_second());
+        if from >= to {
+            Some(value.div_euclid(from / to))
+        } else {
+            value.checked_mul(to / from)
+        }
+    }
+
+    /// Split a value into whole seconds and nanoseconds, rounding the seconds down so
+    /// that values before the epoch keep a positive fraction.
+    fn split(self, value: i64) -> (i64, u32) {
+        let per_second = self.per_second();
+        let nanos = value.rem_euclid(per_second) * (1_000_000_000 / per_second);
+        (value.div_euclid(per_second), nanos as u32)
+    }
+
+    /// `strftime` fraction of a second, with the digits of the unit.
+    fn fraction(self) -> &'static str {
+        match self {
+            Unit::Millis => "%.3f",
+            Unit::Micros => "%.6f",
+            Unit::Nanos => "%.9f",
+        }
+    }
+
+    fn seconds_format(self) -> SecondsFormat {
+        match self {
+            Unit::Millis => SecondsFormat::Millis,
+            Unit::Micros => SecondsFormat::Micros,
+            Unit::Nanos => SecondsFormat::Nanos,
+        }
+    }
+}
+
+impl From<&TimeUnit> for Unit {
+    fn from(unit: &TimeUnit) -> Self {
+        match unit {
+            TimeUnit::MILLIS(_) => Unit::Millis,
+            TimeUnit::MICROS(_) => Unit::Micros,
+            TimeUnit::NANOS(_) => Unit::Nanos,
+        }
+    }
+}
+
+/// A timestamp or time of day column.
+///
+/// `utc` is the `isAdjustedToUTC` flag: values that are not adjusted are local date-times
+/// and are written without an offset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Temporal {
+    Timestamp { unit: Unit, utc: bool },
+    Time { unit: Unit, utc: bool },
+}
+
+impl Temporal {
+    /// Temporal type of a column, from its logical type or its legacy converted type.
+    pub(crate) fn of(column: &Type) -> Option<Self> {
+        if !column.is_primitive() {
+            return None;
+        }
+        let info = column.get_basic_info();
+        match info.logical_type() {
+            Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c,
+                unit,
+            }) => Some(Temporal::Timestamp {
+                unit: Unit::from(&unit),
+                utc: is_adjusted_to_u_t_c,
+            }),
+            Some(LogicalType::Time {
+                is_adjusted_to_u_t_c,
+                unit,
+            }) => Some(Temporal::Time {
+                unit: Unit::from(&unit),
+                utc: is_adjusted_to_u_t_c,
+            }),
+            Some(_) => None,
+            // Legacy converted types are adjusted to UTC
+            None => match info.converted_type() {
+                ConvertedType::TIMESTAMP_MILLIS => Some(Temporal::Timestamp {
+                    unit: Unit::Millis,
+                    utc: true,
+                }),
+                ConvertedType::TIMESTAMP_MICROS => Some(Temporal::Timestamp {
+                    unit: Unit::Micros,
+                    utc: true,
+                }),
+                ConvertedType::TIME_MILLIS => Some(Temporal::Time {
+                    unit: Unit::Millis,
+                    utc: true,
+                }),
+                ConvertedType::TIME_MICROS => Some(Temporal::Time {
+                    unit: Unit::Micros,
+                    utc: true,
+                }),
+                _ => None,
+            },
+        }
+    }
+
+    /// Convert a stored value into JSON.
+    pub(crate) fn to_json(self, value: i64, format: &TimestampFormat) -> Value {
+        let (Temporal::Timestamp { unit, utc } | Temporal::Time { unit, utc }) = self;
+        match format {
+            TimestampFormat::EpochMillis => return epoch_to_json(unit.convert(value, Unit::Millis)),
+            TimestampFormat::EpochMicros => return epoch_to_json(unit.convert(value, Unit::Micros)),
+            TimestampFormat::Display | TimestampFormat::Rfc3339 | TimestampFormat::Custom(_) => {}
+        }
+
+        let (seconds, nanos) = unit.split(value);
+        if let Temporal::Time { .. } = self {
+            let time = u32::try_from(seconds)
+                .ok()
+                .and_then(|seconds| NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos));
+            let Some(time) = time else {
+                return Value::Null;
+            };
+            if let TimestampFormat::Display = format {
+                return Value::String(display(time, utc));
+            }
+            let offset = if utc { "Z" } else { "" };
+            return format_to_json(time.format(&format!("%H:%M:%S{}{}", unit.fraction(), offset)));
+        }
+
+        let Some(datetime) = DateTime::from_timestamp(seconds, nanos) else {
+            return Value::Null;
+        };
+        match (format, utc) {
+            (TimestampFormat::Custom(pattern), true) => format_to_json(datetime.format(pattern)),
+            (TimestampFormat::Custom(pattern), false) => {
+                format_to_json(datetime.naive_utc().format(pattern))
+            }
+            (TimestampFormat::Display, utc) => Value::String(display(datetime.naive_utc(), utc)),
+            (_, true) => Value::String(datetime.to_rfc3339_opts(unit.seconds_format(), true)),
+            (_, false) => format_to_json(
+                datetime
+                    .naive_utc()
+                    .format(&format!("%Y-%m-%dT%H:%M:%S{}", unit.fraction())),
+            ),
+        }
+    }
+}
+
+/// Rebuild a schema with its time of day columns as plain integers.
+///
+/// The row reader cannot decode time of day columns, so they are read as integers and
+/// converted with the temporal type of the file schema.
+pub(crate) fn readable_schema(field: &TypePtr) -> Result<TypePtr> {
+    let info = field.get_basic_info();
+    let readable = if field.is_primitive() {
+        if !matches!(Temporal::of(field), Some(Temporal::Time { .. })) {
+            return Ok(field.clone());
+        }
+        Type::primitive_type_builder(field.name(), field.get_physical_type())
+            .with_repetition(info.repetition())
+            .with_id(info.has_id().then(|| info.id()))
+            .build()
+    } else {
+        let fields = field
+            .get_fields()
+            .iter()
+            .map(readable_schema)
+            .collect::<Result<Vec<_>>>()?;
+        let mut builder = Type::group_type_builder(field.name())
+            .with_converted_type(info.converted_type())
+            .with_logical_type(info.logical_type())
+            .with_id(info.has_id().then(|| info.id()))
+            .with_fields(fields);
+        if info.has_repetition() {
+            builder = builder.with_repetition(info.repetition());
+        }
+        builder.build()
+    };
+
+    readable
+        .map(Arc::new)
+        .map_err(|err| eyre!("cannot build readable schema: {}", err))
+}
+
+/// Display form, with the ` UTC` suffix of `chrono` for values adjusted to UTC.
+fn display(value: impl Display, utc: bool) -> String {
+    match utc {
+        true => format!("{} UTC", value),
+        false => value.to_string(),
+    }
+}
+
+fn epoch_to_json(value: Option<i64>) -> Value {
+    value.map_or(Value::Null, |value| Value::Number(value.into()))
+}
+
+/// Write a formatted value; a pattern the value cannot fill, such as an offset for a
+/// timestamp that is not adjusted to UTC, gives `null`.
+fn format_to_json(formatted: impl Display) -> Value {
+    let mut text = String::new();
+    match write!(text, "{}", formatted) {
+        Ok(()) => Value::String(text),
+        Err(_) => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MILLIS: Temporal = Temporal::Timestamp {
+        unit: Unit::Millis,
+        utc: true,
+    };
+    const MICROS: Temporal = Temporal::Timestamp {
+        unit: Unit::Micros,
+        utc: true,
+    };
+    const LOCAL_NANOS: Temporal = Temporal::Timestamp {
+        unit: Unit::Nanos,
+        utc: false,
+    };
+
+    #[test]
+    fn test_timestamp_units() {
+        let rfc3339 = |temporal: Temporal, value| temporal.to_json(value, &TimestampFormat::Rfc3339);
+
+        assert_eq!(rfc3339(MILLIS, 1_709_280_930_123), json!("2024-03-01T08:15:30.123Z"));
+        assert_eq!(
+            rfc3339(MICROS, 1_709_280_930_123_456),
+            json!("2024-03-01T08:15:30.123456Z")
+        );
+        assert_eq!(
+            rfc3339(LOCAL_NANOS, 1_709_280_930_123_456_789),
+            json!("2024-03-01T08:15:30.123456789")
+        );
+        // Before the epoch
+        assert_eq!(rfc3339(MILLIS, -500), json!("1969-12-31T23:59:59.500Z"));
+        assert_eq!(rfc3339(MICROS, -1), json!("1969-12-31T23:59:59.999999Z"));
+        assert_eq!(rfc3339(MILLIS, i64::MAX), Value::Null);
+    }
+
+    #[test]
+    fn test_time_units() {
+        let time = |unit, utc, value| Temporal::Time { unit, utc }.to_json(value, &TimestampFormat::Rfc3339);
+
+        assert_eq!(time(Unit::Millis, true, 29_730_123), json!("08:15:30.123Z"));
+        assert_eq!(time(Unit::Micros, false, 29_730_123_456), json!("08:15:30.123456"));
+        assert_eq!(time(Unit::Nanos, false, 1), json!("00:00:00.000000001"));
+        assert_eq!(time(Unit::Millis, true, -1), Value::Null);
+    }
+
+    #[test]
+    fn test_display_format() {
+        let display = |temporal: Temporal, value| temporal.to_json(value, &TimestampFormat::default());
+
+        assert_eq!(display(MILLIS, 1_709_280_930_123), json!("2024-03-01 08:15:30.123 UTC"));
+        assert_eq!(display(MICROS, 1_709_280_930_000_000), json!("2024-03-01 08:15:30 UTC"));
+        assert_eq!(display(MICROS, -1), json!("1969-12-31 23:59:59.999999 UTC"));
+        assert_eq!(
+            display(LOCAL_NANOS, 1_709_280_930_123_456_789),
+            json!("2024-03-01 08:15:30.123456789")
+        );
+        let time = Temporal::Time {
+            unit: Unit::Millis,
+            utc: true,
+        };
+        assert_eq!(display(time, 29_730_123), json!("08:15:30.123 UTC"));
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        let format = |format: &str| TimestampFormat::try_from(format.to_string()).unwrap();
+
+        assert_eq!(MICROS.to_json(-1, &format("epoch_millis")), json!(-1));
+        assert_eq!(MILLIS.to_json(1_500, &format("epoch_micros")), json!(1_500_000));
+        assert_eq!(LOCAL_NANOS.to_json(1_999_999, &format("EPOCH_MICROS")), json!(1_999));
+        assert_eq!(MILLIS.to_json(i64::MAX, &format("epoch_micros")), Value::Null);
+        assert_eq!(
+            MILLIS.to_json(1_709_280_930_123, &format("%d/%m/%Y %H:%M %z")),
+            json!("01/03/2024 08:15 +0000")
+        );
+        // Local timestamps have no offset
+        assert_eq!(LOCAL_NANOS.to_json(0, &format("%Y-%m-%d %z")), Value::Null);
+        assert_eq!(LOCAL_NANOS.to_json(0, &format("%Y-%m-%d")), json!("1970-01-01"));
+
+        assert!(TimestampFormat::try_from("iso".to_string()).is_err());
+        assert!(TimestampFormat::try_from("%Y-%Q".to_string()).is_err());
+    }
+}